@@ -0,0 +1,455 @@
+use std::{
+    error::Error,
+    fs,
+    io::{self, Read},
+    path::{Path, PathBuf},
+    process::ExitCode,
+    sync::mpsc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use clap::{Args, Parser, Subcommand};
+#[cfg(target_os = "linux")]
+use dot_dash::desktop::{Selection, read_from_selection};
+use dot_dash::{
+    alphabet::{Alphabet, Table},
+    contest::{Contest, Exchange, PileUp},
+    decoder::{decode_wav, decode_wav_from},
+    desktop::{AudioOutput, play_morse, play_samples},
+    playback::PlaybackOutcome,
+    render::{
+        Carrier, Noise, Qsb, SampleFormat, Transmission, WavFormat, simulate, to_wav_file,
+        write_wav_file,
+    },
+    timing::Timing,
+    tone::Tone,
+    translate::{Translation, decode_with, encode_with},
+};
+use morsify::Options;
+
+/// Translate text to and from Morse code, play it, or render it to audio.
+#[derive(Debug, Parser)]
+#[command(name = "dot-dash", version)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Translate text to Morse code.
+    Encode {
+        #[command(flatten)]
+        input: Input,
+        #[command(flatten)]
+        symbols: Symbols,
+    },
+    /// Translate Morse code, or a WAV recording of it, to text.
+    Decode {
+        #[command(flatten)]
+        input: Input,
+        #[command(flatten)]
+        symbols: Symbols,
+        /// Read the input as a WAV recording of CW instead of Morse code text.
+        #[arg(long, conflicts_with = "paste")]
+        wav: bool,
+        /// Tone frequency of the recording in hertz; found automatically if
+        /// omitted.
+        #[arg(long, requires = "wav")]
+        frequency: Option<f32>,
+    },
+    /// Play text, or Morse code with `--morse`, through the speakers.
+    Play {
+        #[command(flatten)]
+        input: Input,
+        #[command(flatten)]
+        symbols: Symbols,
+        #[command(flatten)]
+        sound: Sound,
+        #[command(flatten)]
+        band: Band,
+        /// Name of the audio output device to play on.
+        #[arg(long)]
+        device: Option<String>,
+    },
+    /// Render text, or Morse code with `--morse`, to a WAV file.
+    Render {
+        #[command(flatten)]
+        input: Input,
+        #[command(flatten)]
+        symbols: Symbols,
+        #[command(flatten)]
+        sound: Sound,
+        #[command(flatten)]
+        band: Band,
+        /// WAV file to write.
+        #[arg(short, long)]
+        output: PathBuf,
+        /// Sample rate of the WAV file in hertz.
+        #[arg(long, default_value_t = 44100)]
+        sample_rate: u32,
+        /// Write 32-bit float samples instead of 16-bit PCM.
+        #[arg(long)]
+        float: bool,
+    },
+}
+
+#[derive(Debug, Args)]
+struct Input {
+    /// File to read; standard input is read if omitted or `-`.
+    file: Option<PathBuf>,
+    /// Read the input from the clipboard instead.
+    #[arg(long, conflicts_with = "file")]
+    paste: bool,
+    /// With `--paste`, read the primary selection rather than the clipboard.
+    #[arg(long, requires = "paste")]
+    primary: bool,
+}
+
+impl Input {
+    fn read(&self) -> Result<String, Box<dyn Error>> {
+        if self.paste {
+            return paste(self.primary);
+        }
+
+        match self.file.as_deref() {
+            Some(path) if path != Path::new("-") => Ok(fs::read_to_string(path)?),
+            _ => {
+                let mut input = String::new();
+                io::stdin().read_to_string(&mut input)?;
+                Ok(input)
+            }
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn paste(primary: bool) -> Result<String, Box<dyn Error>> {
+    let selection = if primary {
+        Selection::Primary
+    } else {
+        Selection::Clipboard
+    };
+    Ok(read_from_selection(selection)?)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn paste(_primary: bool) -> Result<String, Box<dyn Error>> {
+    Err("reading the clipboard is only supported on Linux".into())
+}
+
+/// The characters Morse code is written with, and how text maps to it.
+#[derive(Debug, Args)]
+struct Symbols {
+    #[arg(long, default_value_t = '.')]
+    dot: char,
+    #[arg(long, default_value_t = '-')]
+    dash: char,
+    /// Marks the gap between words.
+    #[arg(long, default_value_t = '/')]
+    space: char,
+    /// Separates characters and words.
+    #[arg(long, default_value_t = ' ')]
+    separator: char,
+    /// Send prosigns written as `<AR>` as one character, and decode known
+    /// prosigns back to that notation.
+    #[arg(long)]
+    prosigns: bool,
+    /// TOML or JSON file of extra or overriding characters.
+    #[arg(long, value_name = "FILE")]
+    table: Option<PathBuf>,
+    /// Script of the text: latin, cyrillic, greek or wabun. Detected from
+    /// the text when encoding if omitted.
+    #[arg(long, value_name = "NAME", value_parser = parse_alphabet)]
+    alphabet: Option<Alphabet>,
+}
+
+fn parse_alphabet(id: &str) -> Result<Alphabet, String> {
+    Alphabet::from_id(id).ok_or_else(|| {
+        let ids: Vec<&str> = Alphabet::ALL.iter().map(|alphabet| alphabet.id()).collect();
+        format!("expected one of {}", ids.join(", "))
+    })
+}
+
+impl Symbols {
+    fn options(&self) -> Options {
+        Options {
+            dot: self.dot,
+            dash: self.dash,
+            space: self.space,
+            separator: self.separator,
+            ..Default::default()
+        }
+    }
+
+    /// How to translate `input`, whose alphabet is detected unless one was
+    /// given.
+    fn translation(&self, input: &str) -> Result<Translation, Box<dyn Error>> {
+        let alphabet = self
+            .alphabet
+            .or_else(|| Alphabet::detect(input))
+            .unwrap_or_default();
+        let table = self.table.as_deref().map(Table::from_file).transpose()?;
+        if let Some(table) = &table {
+            table.check_alphabet(alphabet)?;
+        }
+        Ok(Translation {
+            prosigns: self.prosigns,
+            table,
+            alphabet,
+        })
+    }
+}
+
+#[derive(Debug, Args)]
+struct Sound {
+    /// The input is Morse code rather than text.
+    #[arg(long)]
+    morse: bool,
+    /// Character speed in words per minute.
+    #[arg(long, default_value_t = 20)]
+    wpm: u32,
+    /// Overall speed in words per minute, stretching the gaps between
+    /// characters and words; defaults to `--wpm`.
+    #[arg(long)]
+    farnsworth_wpm: Option<u32>,
+    /// Tone frequency in hertz.
+    #[arg(long, default_value_t = 700.0)]
+    frequency: f32,
+}
+
+impl Sound {
+    fn timing(&self) -> Timing {
+        Timing::farnsworth(self.wpm, self.farnsworth_wpm.unwrap_or(self.wpm))
+    }
+
+    fn tone(&self) -> Tone {
+        Tone::new(self.frequency)
+    }
+
+    /// The input as Morse code written with `symbols`.
+    fn morse(&self, input: &str, symbols: &Symbols) -> Result<String, Box<dyn Error>> {
+        Ok(if self.morse {
+            input.trim().to_string()
+        } else {
+            encode_with(
+                input.trim(),
+                &symbols.options(),
+                &symbols.translation(input)?,
+            )
+        })
+    }
+}
+
+/// The sample rate band simulations are played at.
+const BAND_SAMPLE_RATE: u32 = 44100;
+
+/// The level of `--qrm` carriers relative to the signal.
+const QRM_LEVEL: f32 = 0.3;
+
+#[derive(Debug, Args)]
+struct Band {
+    /// Add noise at this signal-to-noise ratio in decibels.
+    #[arg(long, value_name = "DB", allow_hyphen_values = true)]
+    snr: Option<f32>,
+    /// With `--snr`, make the noise pink rather than white.
+    #[arg(long, requires = "snr")]
+    pink: bool,
+    /// Fade the signals in and out (QSB), peaking about this many seconds
+    /// apart.
+    #[arg(long, value_name = "SECONDS")]
+    qsb: Option<f32>,
+    /// Add an interfering carrier (QRM) at this frequency in hertz; repeat
+    /// for more carriers.
+    #[arg(long, value_name = "HZ")]
+    qrm: Vec<f32>,
+    /// Add this many other stations calling CQ at nearby pitches and speeds.
+    #[arg(long, default_value_t = 0)]
+    stations: usize,
+    /// Seed for the noise, fading and other stations; random if omitted.
+    #[arg(long)]
+    seed: Option<u64>,
+}
+
+impl Band {
+    /// Whether any band conditions were asked for, rather than a clean
+    /// signal.
+    fn is_set(&self) -> bool {
+        self.snr.is_some() || self.qsb.is_some() || !self.qrm.is_empty() || self.stations > 0
+    }
+
+    /// `morse` played with `sound` on the band, with the other stations
+    /// around it, as samples at `sample_rate`.
+    fn simulate(
+        &self,
+        morse: &str,
+        options: &Options,
+        sound: &Sound,
+        sample_rate: u32,
+    ) -> Result<Vec<f32>, Box<dyn Error>> {
+        let seed = match self.seed {
+            Some(seed) => seed,
+            None => SystemTime::now().duration_since(UNIX_EPOCH)?.as_nanos() as u64,
+        };
+
+        let mut signals = vec![Transmission {
+            morse_code: morse.to_string(),
+            tone: sound.tone(),
+            timing: sound.timing(),
+            delay: Duration::ZERO,
+            level: 1.0,
+        }];
+        let mut contest = Contest::new(Exchange::default(), seed);
+        let pile_up = PileUp {
+            callers: self.stations,
+            pitch: sound.frequency,
+            ..PileUp::default()
+        };
+        signals.extend(contest.pile_up(&pile_up).into_iter().map(|caller| {
+            let call = format!("CQ TEST {}", caller.station.callsign);
+            caller.transmission(&call, options)
+        }));
+
+        let qsb = match self.qsb {
+            Some(period) => Some(Qsb {
+                period: Duration::try_from_secs_f32(period)
+                    .map_err(|err| format!("invalid QSB period {period}: {err}"))?,
+                ..Qsb::default()
+            }),
+            None => None,
+        };
+        let band = dot_dash::render::Band {
+            signals,
+            noise: if self.pink { Noise::Pink } else { Noise::White },
+            snr: self.snr,
+            qsb,
+            qrm: self
+                .qrm
+                .iter()
+                .map(|&frequency| Carrier {
+                    frequency,
+                    level: QRM_LEVEL,
+                })
+                .collect(),
+            duration: Duration::ZERO,
+            seed,
+        };
+        Ok(simulate(&band, options, sample_rate))
+    }
+}
+
+fn run(command: Command) -> Result<(), Box<dyn Error>> {
+    match command {
+        Command::Encode { input, symbols } => {
+            let input = input.read()?;
+            let (options, translation) = (symbols.options(), symbols.translation(&input)?);
+            for line in input.lines() {
+                println!("{}", encode_with(line, &options, &translation));
+            }
+        }
+        Command::Decode {
+            input,
+            symbols,
+            wav,
+            frequency,
+        } => {
+            let options = symbols.options();
+            if wav {
+                let decoded = match input.file.as_deref() {
+                    Some(path) if path != Path::new("-") => decode_wav(path, frequency, &options)?,
+                    _ => decode_wav_from(io::stdin().lock(), frequency, &options)?,
+                };
+                let translation = symbols.translation(&decoded.morse)?;
+                println!("{}", decode_with(&decoded.morse, &options, &translation));
+            } else {
+                let input = input.read()?;
+                let translation = symbols.translation(&input)?;
+                for line in input.lines() {
+                    println!("{}", decode_with(line, &options, &translation));
+                }
+            }
+        }
+        Command::Play {
+            input,
+            symbols,
+            sound,
+            band,
+            device,
+        } => {
+            let morse = sound.morse(&input.read()?, &symbols)?;
+            let output = AudioOutput {
+                device,
+                ..AudioOutput::default()
+            };
+            let (outcome_tx, outcome_rx) = mpsc::channel();
+            let onend = move |outcome| {
+                let _ = outcome_tx.send(outcome);
+            };
+            let handle = if band.is_set() {
+                let samples =
+                    band.simulate(&morse, &symbols.options(), &sound, BAND_SAMPLE_RATE)?;
+                play_samples(samples, BAND_SAMPLE_RATE, &output, onend)?
+            } else {
+                play_morse(
+                    &morse,
+                    symbols.options(),
+                    sound.tone(),
+                    sound.timing(),
+                    &output,
+                    onend,
+                )?
+            };
+            handle.join();
+
+            if let Ok(PlaybackOutcome::Failed(err)) = outcome_rx.recv() {
+                return Err(err.into());
+            }
+        }
+        Command::Render {
+            input,
+            symbols,
+            sound,
+            band,
+            output,
+            sample_rate,
+            float,
+        } => {
+            let morse = sound.morse(&input.read()?, &symbols)?;
+            let format = WavFormat {
+                sample_rate,
+                sample_format: if float {
+                    SampleFormat::Float32
+                } else {
+                    SampleFormat::Pcm16
+                },
+            };
+            if band.is_set() {
+                let samples = band.simulate(&morse, &symbols.options(), &sound, sample_rate)?;
+                write_wav_file(output, samples, format)?;
+            } else {
+                to_wav_file(
+                    output,
+                    &morse,
+                    &symbols.options(),
+                    sound.tone(),
+                    sound.timing(),
+                    format,
+                )?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+
+    match run(cli.command) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("dot-dash: {err}");
+            ExitCode::FAILURE
+        }
+    }
+}
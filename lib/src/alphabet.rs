@@ -0,0 +1,691 @@
+use std::{collections::BTreeMap, fs, path::Path};
+
+use morsify::MorseCode;
+use serde::Deserialize;
+
+use crate::{Error, Result};
+
+/// The letters of International Morse code.
+const LATIN: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+
+/// The digits and punctuation every alphabet shares, whose code a [`Table`]
+/// may not reuse for another character unless it overrides them as well.
+const SHARED: &str = "0123456789.,?'!/()&:;=+-_\"$@";
+
+/// Russian Morse code. `Ё` is sent as `Е`, so it decodes as `Е`.
+const CYRILLIC: &[(char, &str)] = &[
+    ('А', ".-"),
+    ('Б', "-..."),
+    ('В', ".--"),
+    ('Г', "--."),
+    ('Д', "-.."),
+    ('Е', "."),
+    ('Ж', "...-"),
+    ('З', "--.."),
+    ('И', ".."),
+    ('Й', ".---"),
+    ('К', "-.-"),
+    ('Л', ".-.."),
+    ('М', "--"),
+    ('Н', "-."),
+    ('О', "---"),
+    ('П', ".--."),
+    ('Р', ".-."),
+    ('С', "..."),
+    ('Т', "-"),
+    ('У', "..-"),
+    ('Ф', "..-."),
+    ('Х', "...."),
+    ('Ц', "-.-."),
+    ('Ч', "---."),
+    ('Ш', "----"),
+    ('Щ', "--.-"),
+    ('Ъ', "--.--"),
+    ('Ы', "-.--"),
+    ('Ь', "-..-"),
+    ('Э', "..-.."),
+    ('Ю', "..--"),
+    ('Я', ".-.-"),
+    ('Ё', "."),
+];
+
+/// Greek Morse code. Letters with a tonos are sent as the plain letter.
+const GREEK: &[(char, &str)] = &[
+    ('Α', ".-"),
+    ('Β', "-..."),
+    ('Γ', "--."),
+    ('Δ', "-.."),
+    ('Ε', "."),
+    ('Ζ', "--.."),
+    ('Η', "...."),
+    ('Θ', "-.-."),
+    ('Ι', ".."),
+    ('Κ', "-.-"),
+    ('Λ', ".-.."),
+    ('Μ', "--"),
+    ('Ν', "-."),
+    ('Ξ', "-..-"),
+    ('Ο', "---"),
+    ('Π', ".--."),
+    ('Ρ', ".-."),
+    ('Σ', "..."),
+    ('Τ', "-"),
+    ('Υ', "-.--"),
+    ('Φ', "..-."),
+    ('Χ', "----"),
+    ('Ψ', "--.-"),
+    ('Ω', ".--"),
+    ('Ά', ".-"),
+    ('Έ', "."),
+    ('Ή', "...."),
+    ('Ί', ".."),
+    ('Ό', "---"),
+    ('Ύ', "-.--"),
+    ('Ώ', ".--"),
+];
+
+/// Wabun code, for katakana.
+const WABUN: &[(char, &str)] = &[
+    ('イ', ".-"),
+    ('ロ', ".-.-"),
+    ('ハ', "-..."),
+    ('ニ', "-.-."),
+    ('ホ', "-.."),
+    ('ヘ', "."),
+    ('ト', "..-.."),
+    ('チ', "..-."),
+    ('リ', "--."),
+    ('ヌ', "...."),
+    ('ル', "-.--."),
+    ('ヲ', ".---"),
+    ('ワ', "-.-"),
+    ('カ', ".-.."),
+    ('ヨ', "--"),
+    ('タ', "-."),
+    ('レ', "---"),
+    ('ソ', "---."),
+    ('ツ', ".--."),
+    ('ネ', "--.-"),
+    ('ナ', ".-."),
+    ('ラ', "..."),
+    ('ム', "-"),
+    ('ウ', "..-"),
+    ('ヰ', ".-..-"),
+    ('ノ', "..--"),
+    ('オ', ".-..."),
+    ('ク', "...-"),
+    ('ヤ', ".--"),
+    ('マ', "-..-"),
+    ('ケ', "-.--"),
+    ('フ', "--.."),
+    ('コ', "----"),
+    ('エ', "-.---"),
+    ('テ', ".-.--"),
+    ('ア', "--.--"),
+    ('サ', "-.-.-"),
+    ('キ', "-.-.."),
+    ('ユ', "-..--"),
+    ('メ', "-...-"),
+    ('ミ', "..-.-"),
+    ('シ', "--.-."),
+    ('ヱ', ".--.."),
+    ('ヒ', "--..-"),
+    ('モ', "-..-."),
+    ('セ', ".---."),
+    ('ス', "---.-"),
+    ('ン', ".-.-."),
+    ('゛', ".."),
+    ('゜', "..--."),
+    ('ー', ".--.-"),
+    ('、', ".-.-.-"),
+];
+
+/// Katakana written with a dakuten, and the kana they are written on.
+const VOICED: [(&str, &str); 2] = [
+    (
+        "ガギグゲゴザジズゼゾダヂヅデドバビブベボヴ",
+        "カキクケコサシスセソタチツテトハヒフヘホウ",
+    ),
+    ("パピプペポ", "ハヒフヘホ"),
+];
+
+/// Small katakana, and the full-size kana Wabun sends them as.
+const SMALL: (&str, &str) = ("ァィゥェォッャュョヮ", "アイウエオツヤユヨワ");
+
+/// The dakuten and handakuten, as sent in Wabun after the kana they mark.
+const MARKS: [char; 2] = ['゛', '゜'];
+
+/// The layout of a table file.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TableFile {
+    characters: BTreeMap<String, String>,
+}
+
+/// Extra or overriding Morse code mappings, such as national characters or a
+/// club's own codes, used alongside the built-in alphabet.
+///
+/// Patterns are written with `.` and `-`. Letters are matched regardless of
+/// case. A table is checked when it is built, so every character has one
+/// pattern and every pattern decodes to one character, and against the
+/// letters of the alphabet it is used with by [`Table::check_alphabet`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Table {
+    characters: BTreeMap<char, String>,
+}
+
+impl Table {
+    /// Builds a table from characters and their patterns.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Table`] if a pattern is empty or holds anything but
+    /// dots and dashes, if a character is given two patterns, or if a pattern
+    /// is used by two characters, including a digit or punctuation mark the
+    /// table does not override.
+    pub fn new(characters: impl IntoIterator<Item = (char, String)>) -> Result<Self> {
+        let mut table = BTreeMap::new();
+        for (char, pattern) in characters {
+            if pattern.is_empty() || !pattern.chars().all(|symbol| matches!(symbol, '.' | '-')) {
+                return Err(Error::Table(format!(
+                    "the pattern {pattern:?} of {char:?} may only contain dots and dashes"
+                )));
+            }
+
+            match table.insert(fold_case(char), pattern.clone()) {
+                Some(previous) if previous != pattern => {
+                    return Err(Error::Table(format!(
+                        "{char:?} is given both {previous} and {pattern}"
+                    )));
+                }
+                _ => {}
+            }
+        }
+
+        let mut patterns = BTreeMap::new();
+        for (&char, pattern) in &table {
+            if let Some(other) = patterns.insert(pattern.as_str(), char) {
+                return Err(Error::Table(format!(
+                    "{other:?} and {char:?} both use {pattern}"
+                )));
+            }
+        }
+
+        let table = Self { characters: table };
+        table.check_reserved(&built_in(SHARED))?;
+        Ok(table)
+    }
+
+    /// Parses a TOML table, with a pattern for each character under
+    /// `[characters]`.
+    ///
+    /// ```toml
+    /// [characters]
+    /// "Ä" = ".-.-"
+    /// "Ö" = "---."
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Table`] if the table cannot be parsed or its
+    /// mappings conflict, as for [`Table::new`].
+    pub fn from_toml(source: &str) -> Result<Self> {
+        let file: TableFile =
+            toml::from_str(source).map_err(|err| Error::Table(err.to_string()))?;
+        Self::from_file_contents(file)
+    }
+
+    /// Parses a JSON table, laid out like the TOML one:
+    /// `{ "characters": { "Ä": ".-.-" } }`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Table`] if the table cannot be parsed or its
+    /// mappings conflict, as for [`Table::new`].
+    pub fn from_json(source: &str) -> Result<Self> {
+        let file: TableFile =
+            serde_json::from_str(source).map_err(|err| Error::Table(err.to_string()))?;
+        Self::from_file_contents(file)
+    }
+
+    /// Reads a table file, parsed as JSON if its extension is `.json` and
+    /// as TOML otherwise.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Table`] if the file cannot be read or parsed, or its
+    /// mappings conflict.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let source = fs::read_to_string(path)
+            .map_err(|err| Error::Table(format!("cannot read {}: {err}", path.display())))?;
+
+        if path
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("json"))
+        {
+            Self::from_json(&source)
+        } else {
+            Self::from_toml(&source)
+        }
+    }
+
+    fn from_file_contents(file: TableFile) -> Result<Self> {
+        let characters = file
+            .characters
+            .into_iter()
+            .map(|(key, pattern)| {
+                let mut chars = key.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(char), None) => Ok((char, pattern)),
+                    _ => Err(Error::Table(format!("{key:?} is not a single character"))),
+                }
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Self::new(characters)
+    }
+
+    /// The pattern of `char`, if the table defines it.
+    #[must_use]
+    pub fn pattern(&self, char: char) -> Option<&str> {
+        self.characters.get(&fold_case(char)).map(String::as_str)
+    }
+
+    /// The character the table assigns to `pattern`, if any.
+    #[must_use]
+    pub fn character(&self, pattern: &str) -> Option<char> {
+        self.characters
+            .iter()
+            .find(|(_, candidate)| *candidate == pattern)
+            .map(|(&char, _)| char)
+    }
+
+    /// The characters of the table and their patterns, in character order.
+    pub fn iter(&self) -> impl Iterator<Item = (char, &str)> {
+        self.characters
+            .iter()
+            .map(|(&char, pattern)| (char, pattern.as_str()))
+    }
+
+    /// Checks the table against the letters of the `alphabet` it is used
+    /// with, as [`Table::new`] checks it against the built-in characters.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Table`] if the table gives a character a pattern
+    /// that stands for a letter of `alphabet` the table does not override.
+    pub fn check_alphabet(&self, alphabet: Alphabet) -> Result<()> {
+        let letters = match alphabet {
+            Alphabet::Latin => built_in(LATIN),
+            _ => alphabet
+                .letters()
+                .iter()
+                .map(|&(letter, pattern)| (letter, pattern.to_string()))
+                .collect(),
+        };
+        self.check_reserved(&letters)
+    }
+
+    /// Checks that no character of the table uses the pattern of one of
+    /// the `reserved` characters, unless the table overrides that character
+    /// or gives its own character the same pattern.
+    fn check_reserved(&self, reserved: &[(char, String)]) -> Result<()> {
+        for (letter, pattern) in reserved {
+            if self.pattern(*letter).is_some() {
+                continue;
+            }
+            if let Some(char) = self.character(pattern)
+                && !reserved.contains(&(char, pattern.clone()))
+            {
+                return Err(Error::Table(format!(
+                    "{char:?} uses {pattern}, which already stands for {letter:?}"
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A script that text is written in, and the Morse code sent for its
+/// letters. Digits and punctuation are shared with the Latin alphabet.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Alphabet {
+    /// International Morse code.
+    #[default]
+    Latin,
+    /// Russian Cyrillic.
+    Cyrillic,
+    /// Greek.
+    Greek,
+    /// Japanese kana in Wabun code. Hiragana is sent as katakana, and
+    /// decodes as katakana.
+    Wabun,
+}
+
+impl Alphabet {
+    pub const ALL: [Self; 4] = [Self::Latin, Self::Cyrillic, Self::Greek, Self::Wabun];
+
+    /// A short lower-case name, for command-line arguments and settings.
+    #[must_use]
+    pub const fn id(self) -> &'static str {
+        match self {
+            Self::Latin => "latin",
+            Self::Cyrillic => "cyrillic",
+            Self::Greek => "greek",
+            Self::Wabun => "wabun",
+        }
+    }
+
+    /// The alphabet with the given [`id`](Self::id).
+    #[must_use]
+    pub fn from_id(id: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|alphabet| alphabet.id() == id)
+    }
+
+    /// The name to show in a user interface.
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Latin => "Latin",
+            Self::Cyrillic => "Cyrillic",
+            Self::Greek => "Greek",
+            Self::Wabun => "Japanese (Wabun)",
+        }
+    }
+
+    /// The alphabet most letters of `text` belong to, or `None` if it has
+    /// no letters. A tie goes to the alphabet first in [`Alphabet::ALL`].
+    #[must_use]
+    pub fn detect(text: &str) -> Option<Self> {
+        let mut counts = [0_usize; Self::ALL.len()];
+        for char in text.chars() {
+            if let Some(alphabet) = Self::of(char) {
+                counts[alphabet as usize] += 1;
+            }
+        }
+
+        let (index, &count) = counts
+            .iter()
+            .enumerate()
+            .rev()
+            .max_by_key(|&(_, count)| count)?;
+        (count > 0).then_some(Self::ALL[index])
+    }
+
+    /// The alphabet a letter belongs to.
+    fn of(char: char) -> Option<Self> {
+        match char {
+            'A'..='Z' | 'a'..='z' => Some(Self::Latin),
+            '\u{0400}'..='\u{04FF}' => Some(Self::Cyrillic),
+            '\u{0370}'..='\u{03FF}' => Some(Self::Greek),
+            '\u{3041}'..='\u{30FF}' => Some(Self::Wabun),
+            _ => None,
+        }
+    }
+
+    /// The letters of the alphabet and their patterns, beyond those of
+    /// International Morse code.
+    const fn letters(self) -> &'static [(char, &'static str)] {
+        match self {
+            Self::Latin => &[],
+            Self::Cyrillic => CYRILLIC,
+            Self::Greek => GREEK,
+            Self::Wabun => WABUN,
+        }
+    }
+
+    /// The patterns `char` is sent as, if it is a letter of the alphabet.
+    /// That is one pattern, except for a voiced kana, which Wabun sends as
+    /// the plain kana followed by its mark.
+    #[must_use]
+    pub fn encode(self, char: char) -> Option<Vec<&'static str>> {
+        let find = |char| {
+            self.letters()
+                .iter()
+                .find(|&&(letter, _)| letter == char)
+                .map(|&(_, pattern)| pattern)
+        };
+
+        if self != Self::Wabun {
+            return find(fold_case(char)).map(|pattern| vec![pattern]);
+        }
+
+        let kana = katakana(char);
+        match unvoice(kana) {
+            Some((kana, mark)) => Some(vec![find(kana)?, find(mark)?]),
+            None => find(kana).map(|pattern| vec![pattern]),
+        }
+    }
+
+    /// The letter of the alphabet sent as `pattern`, if any.
+    #[must_use]
+    pub fn decode(self, pattern: &str) -> Option<char> {
+        self.letters()
+            .iter()
+            .find(|&&(_, candidate)| candidate == pattern)
+            .map(|&(letter, _)| letter)
+    }
+
+    /// Joins each kana in decoded `text` with the voicing mark sent after it.
+    pub(crate) fn compose(self, text: &str) -> String {
+        if self != Self::Wabun {
+            return text.to_string();
+        }
+
+        let mut composed = String::with_capacity(text.len());
+        for char in text.chars() {
+            match composed.chars().last().and_then(|kana| voice(kana, char)) {
+                Some(voiced) => {
+                    composed.pop();
+                    composed.push(voiced);
+                }
+                None => composed.push(char),
+            }
+        }
+        composed
+    }
+}
+
+/// The katakana for a hiragana `char`, and the full-size kana for a small
+/// one; anything else is returned unchanged.
+fn katakana(char: char) -> char {
+    let char = match char {
+        '\u{3041}'..='\u{3096}' => char::from_u32(char as u32 + 0x60).unwrap_or(char),
+        _ => char,
+    };
+    let (small, full) = SMALL;
+    small
+        .chars()
+        .position(|candidate| candidate == char)
+        .and_then(|index| full.chars().nth(index))
+        .unwrap_or(char)
+}
+
+/// The plain kana and mark a voiced katakana is written with.
+fn unvoice(kana: char) -> Option<(char, char)> {
+    VOICED
+        .iter()
+        .zip(MARKS)
+        .find_map(|(&(voiced, plain), mark)| {
+            let index = voiced.chars().position(|candidate| candidate == kana)?;
+            Some((plain.chars().nth(index)?, mark))
+        })
+}
+
+/// The katakana written as `kana` with `mark`, if there is one.
+fn voice(kana: char, mark: char) -> Option<char> {
+    let (voiced, plain) = MARKS
+        .iter()
+        .position(|&candidate| candidate == mark)
+        .map(|index| VOICED[index])?;
+    let index = plain.chars().position(|char| char == kana)?;
+    voiced.chars().nth(index)
+}
+
+/// The characters of International Morse code in `chars`, with their
+/// patterns.
+fn built_in(chars: &str) -> Vec<(char, String)> {
+    let code = MorseCode::default();
+    chars
+        .chars()
+        .map(|char| (char, code.encode(char.to_string()).trim().to_string()))
+        .collect()
+}
+
+/// The upper case of `char`, where it is a single character.
+fn fold_case(char: char) -> char {
+    let mut upper = char.to_uppercase();
+    match (upper.next(), upper.next()) {
+        (Some(upper), None) => upper,
+        _ => char,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use morsify::Options;
+
+    use super::*;
+    use crate::translate::{Translation, decode_with, encode_with};
+
+    fn table(characters: &[(char, &str)]) -> Result<Table> {
+        Table::new(
+            characters
+                .iter()
+                .map(|&(char, pattern)| (char, pattern.to_string())),
+        )
+    }
+
+    #[test]
+    fn table_matches_letters_regardless_of_case() {
+        let table = table(&[('Ä', ".-.-"), ('ö', "---.")]).unwrap();
+
+        assert_eq!(table.pattern('ä'), Some(".-.-"));
+        assert_eq!(table.pattern('Ö'), Some("---."));
+        assert_eq!(table.character("---."), Some('Ö'));
+    }
+
+    #[test]
+    fn table_rejects_conflicts() {
+        assert!(matches!(table(&[('Ä', ".-x")]), Err(Error::Table(_))));
+        assert!(matches!(table(&[('Ä', "")]), Err(Error::Table(_))));
+        assert!(matches!(
+            table(&[('Ä', ".-.-"), ('ä', "---.")]),
+            Err(Error::Table(_))
+        ));
+        assert!(matches!(
+            table(&[('Ä', ".-.-"), ('Ö', ".-.-")]),
+            Err(Error::Table(_))
+        ));
+        // `.----` is the built-in 1, which the table does not override.
+        assert!(matches!(table(&[('Ä', ".----")]), Err(Error::Table(_))));
+        assert!(table(&[('Ä', ".----"), ('1', ".-.-")]).is_ok());
+    }
+
+    #[test]
+    fn table_rejects_conflicts_with_latin_letters() {
+        // `.-` is A, which the table does not override.
+        let table_a = table(&[('Ä', ".-")]).unwrap();
+        assert!(matches!(
+            table_a.check_alphabet(Alphabet::Latin),
+            Err(Error::Table(_))
+        ));
+        let overriding = table(&[('Ä', ".-"), ('A', ".-.-")]).unwrap();
+        assert!(overriding.check_alphabet(Alphabet::Latin).is_ok());
+    }
+
+    #[test]
+    fn table_rejects_conflicts_with_its_alphabet() {
+        // `.-.-` is Я in Cyrillic, and unused in Latin.
+        let table_a = table(&[('Ä', ".-.-")]).unwrap();
+        assert!(table_a.check_alphabet(Alphabet::Latin).is_ok());
+        assert!(matches!(
+            table_a.check_alphabet(Alphabet::Cyrillic),
+            Err(Error::Table(_))
+        ));
+
+        let overriding = table(&[('Ä', ".-.-"), ('Я', "--.---")]).unwrap();
+        assert!(overriding.check_alphabet(Alphabet::Cyrillic).is_ok());
+        let same = table(&[('Е', ".")]).unwrap();
+        assert!(same.check_alphabet(Alphabet::Cyrillic).is_ok());
+    }
+
+    #[test]
+    fn table_files_parse() {
+        let toml = Table::from_toml("[characters]\n\"Ä\" = \".-.-\"\n").unwrap();
+        let json = Table::from_json(r#"{ "characters": { "Ä": ".-.-" } }"#).unwrap();
+        assert_eq!(toml, json);
+
+        assert!(Table::from_json(r#"{ "characters": { "AE": ".-.-" } }"#).is_err());
+        assert!(Table::from_toml("[letters]\n\"Ä\" = \".-.-\"\n").is_err());
+    }
+
+    #[test]
+    fn table_takes_part_in_translation() {
+        let translation = Translation {
+            table: Some(table(&[('Ä', ".-.-")]).unwrap()),
+            ..Translation::default()
+        };
+        let options = Options::default();
+
+        let morse = encode_with("ä", &options, &translation);
+        assert_eq!(decode_with(&morse, &options, &translation), "Ä");
+    }
+
+    fn round_trip(text: &str, alphabet: Alphabet) -> String {
+        let translation = Translation {
+            alphabet,
+            ..Translation::default()
+        };
+        let options = Options::default();
+        decode_with(
+            &encode_with(text, &options, &translation),
+            &options,
+            &translation,
+        )
+    }
+
+    #[test]
+    fn cyrillic_round_trips() {
+        assert_eq!(Alphabet::Cyrillic.encode('ж'), Some(vec!["...-"]));
+        assert_eq!(round_trip("Привет мир", Alphabet::Cyrillic), "ПРИВЕТ МИР");
+        // Ё is sent as Е.
+        assert_eq!(round_trip("ЁЖ", Alphabet::Cyrillic), "ЕЖ");
+    }
+
+    #[test]
+    fn greek_round_trips() {
+        assert_eq!(round_trip("Καλημέρα", Alphabet::Greek), "ΚΑΛΗΜΕΡΑ");
+        assert_eq!(round_trip("ΨΩ 73", Alphabet::Greek), "ΨΩ 73");
+    }
+
+    #[test]
+    fn wabun_sends_voiced_kana_as_two_characters() {
+        assert_eq!(
+            Alphabet::Wabun.encode('ガ'),
+            Some(vec![
+                Alphabet::Wabun.encode('カ').unwrap()[0],
+                Alphabet::Wabun.encode('゛').unwrap()[0]
+            ])
+        );
+        assert_eq!(round_trip("だいがく", Alphabet::Wabun), "ダイガク");
+        assert_eq!(round_trip("パン", Alphabet::Wabun), "パン");
+        // Small kana are sent as full-size ones.
+        assert_eq!(round_trip("キャ", Alphabet::Wabun), "キヤ");
+    }
+
+    #[test]
+    fn detects_the_script_most_letters_are_in() {
+        assert_eq!(Alphabet::detect("Привет, world"), Some(Alphabet::Cyrillic));
+        assert_eq!(Alphabet::detect("ΑΒ AB"), Some(Alphabet::Latin));
+        assert_eq!(Alphabet::detect("ありがとう"), Some(Alphabet::Wabun));
+        assert_eq!(Alphabet::detect("73 !"), None);
+    }
+
+    #[test]
+    fn ids_round_trip() {
+        for alphabet in Alphabet::ALL {
+            assert_eq!(Alphabet::from_id(alphabet.id()), Some(alphabet));
+        }
+    }
+}
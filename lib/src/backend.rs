@@ -0,0 +1,121 @@
+use std::future::Future;
+
+use morsify::Options;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub use crate::desktop::Platform;
+#[cfg(target_arch = "wasm32")]
+pub use crate::web::Platform;
+use crate::{
+    Result,
+    playback::{Completion, ProgressEvents},
+    timing::Timing,
+    tone::Tone,
+};
+
+/// Audio, speech, clipboard and settings access on the platform the crate
+/// was built for.
+///
+/// [`Platform`] implements it for the current target, so front ends can use
+/// one code path instead of switching between the `web`, `desktop` and
+/// `macos` modules. Playback methods return a [`Completion`] to await the
+/// outcome; the clipboard methods are asynchronous because the browser
+/// clipboard is.
+pub trait Backend {
+    /// Plays a Morse code string, stopping any Morse code this backend is
+    /// already playing.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Audio`] if the audio output cannot be opened.
+    ///
+    /// [`Error::Audio`]: crate::Error::Audio
+    fn play_morse(
+        &self,
+        morse_code: &str,
+        options: Options,
+        tone: Tone,
+        timing: Timing,
+    ) -> Result<Completion>;
+
+    /// Plays a Morse code string like [`Backend::play_morse`], also reporting
+    /// each element as it starts sounding.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Audio`] if the audio output cannot be opened.
+    ///
+    /// [`Error::Audio`]: crate::Error::Audio
+    fn play_morse_with_progress(
+        &self,
+        morse_code: &str,
+        options: Options,
+        tone: Tone,
+        timing: Timing,
+    ) -> Result<(Completion, ProgressEvents)>;
+
+    /// Plays mono samples in `-1.0..=1.0` at `sample_rate`, such as a pile-up
+    /// rendered by [`mix`](crate::render::mix), stopping any Morse code this
+    /// backend is already playing.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Audio`] if the audio output cannot be opened.
+    ///
+    /// [`Error::Audio`]: crate::Error::Audio
+    fn play_samples(&self, samples: Vec<f32>, sample_rate: u32) -> Result<Completion>;
+
+    /// Vibrates a Morse code string, keying the motor the way
+    /// [`Backend::play_morse`] keys the tone. The pattern is handed to the
+    /// device and runs on its own until it ends or [`Backend::stop`] cancels
+    /// it.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Haptic`] if the device cannot vibrate.
+    ///
+    /// [`Error::Haptic`]: crate::Error::Haptic
+    fn vibrate_morse(&self, morse_code: &str, options: &Options, timing: &Timing) -> Result<()>;
+
+    /// Reads the given text aloud, cancelling any text already being read.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Speech`] if speech synthesis is unavailable.
+    ///
+    /// [`Error::Speech`]: crate::Error::Speech
+    fn play_text(&self, text: &str) -> Result<Completion>;
+
+    /// Stops the Morse code, vibration and speech started through this
+    /// backend. Their completions resolve to [`PlaybackOutcome::Cancelled`].
+    ///
+    /// [`PlaybackOutcome::Cancelled`]: crate::playback::PlaybackOutcome::Cancelled
+    fn stop(&self);
+
+    /// Copies the given text to the clipboard.
+    fn copy_to_clipboard(&self, text: &str) -> impl Future<Output = Result<()>>;
+
+    /// Reads text from the clipboard.
+    fn read_clipboard(&self) -> impl Future<Output = Result<String>>;
+
+    /// Reads the setting saved under `key` by [`Backend::write_setting`], or
+    /// `None` if nothing was saved.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Storage`] if the saved settings cannot be read.
+    ///
+    /// [`Error::Storage`]: crate::Error::Storage
+    fn read_setting(&self, key: &str) -> Result<Option<String>>;
+
+    /// Saves `value` under `key`, keeping it across restarts of the app. Keys
+    /// are short names made of letters, digits, `-` and `_`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Storage`] if the key is not a valid name or the
+    /// setting cannot be saved.
+    ///
+    /// [`Error::Storage`]: crate::Error::Storage
+    fn write_setting(&self, key: &str, value: &str) -> Result<()>;
+}
@@ -0,0 +1,468 @@
+use std::time::Duration;
+
+use morsify::Options;
+
+use crate::{
+    random::Random,
+    render::{Transmission, mix},
+    timing::Timing,
+    tone::Tone,
+    translate::encode,
+};
+
+/// How the callsigns of a country are formed, and where its stations are.
+struct Country {
+    name: &'static str,
+    /// How often the country is heard, relative to the others.
+    share: f64,
+    /// The letters a callsign starts with, before its digit.
+    prefixes: &'static [&'static str],
+    /// The digits that follow the prefix.
+    digits: &'static str,
+    /// The shortest and longest suffix after the digit, in letters.
+    suffix: (usize, usize),
+    /// The CQ zone of each digit in `digits`, or one zone for all of them.
+    zones: &'static [u8],
+    /// The states or provinces of each digit in `digits`, for countries that
+    /// send them in state exchanges.
+    regions: &'static [&'static [&'static str]],
+}
+
+impl Country {
+    fn zone(&self, digit: usize) -> u8 {
+        self.zones[digit.min(self.zones.len() - 1)]
+    }
+}
+
+const COUNTRIES: &[Country] = &[
+    Country {
+        name: "United States",
+        share: 30.0,
+        prefixes: &[
+            "K", "W", "N", "AA", "AB", "AC", "AD", "AE", "AF", "AG", "AI", "AJ", "AK", "KA", "KB",
+            "KC", "KD", "KE", "KF", "KG", "KI", "KJ", "KK", "KM", "KN", "KO", "WA", "WB", "WD",
+        ],
+        digits: "0123456789",
+        suffix: (2, 3),
+        zones: &[4, 5, 5, 5, 5, 4, 3, 3, 4, 4],
+        regions: &[
+            &["MN", "IA", "MO", "KS", "NE", "SD", "ND", "CO"],
+            &["ME", "NH", "VT", "MA", "RI", "CT"],
+            &["NY", "NJ"],
+            &["PA", "DE", "MD"],
+            &["VA", "NC", "SC", "GA", "FL", "AL", "TN", "KY"],
+            &["MS", "LA", "AR", "OK", "TX", "NM"],
+            &["CA"],
+            &["WA", "OR", "ID", "MT", "WY", "NV", "UT", "AZ"],
+            &["MI", "OH", "WV"],
+            &["WI", "IL", "IN"],
+        ],
+    },
+    Country {
+        name: "Canada",
+        share: 5.0,
+        prefixes: &["VE", "VA"],
+        digits: "1234567",
+        suffix: (2, 3),
+        zones: &[5, 5, 4, 4, 4, 4, 3],
+        regions: &[
+            &["NS"],
+            &["QC"],
+            &["ON"],
+            &["MB"],
+            &["SK"],
+            &["AB"],
+            &["BC"],
+        ],
+    },
+    Country {
+        name: "Germany",
+        share: 10.0,
+        prefixes: &["DL", "DK", "DJ", "DH", "DG", "DF", "DD", "DC", "DB", "DO"],
+        digits: "0123456789",
+        suffix: (2, 3),
+        zones: &[14],
+        regions: &[],
+    },
+    Country {
+        name: "Japan",
+        share: 8.0,
+        prefixes: &["JA", "JE", "JF", "JG", "JH", "JI", "JJ", "JK", "JL", "JR"],
+        digits: "0123456789",
+        suffix: (2, 3),
+        zones: &[25],
+        regions: &[],
+    },
+    Country {
+        name: "Italy",
+        share: 6.0,
+        prefixes: &["I", "IK", "IZ", "IW"],
+        digits: "0123456789",
+        suffix: (2, 3),
+        zones: &[15],
+        regions: &[],
+    },
+    Country {
+        name: "England",
+        share: 6.0,
+        prefixes: &["G", "M", "2E"],
+        digits: "0134567",
+        suffix: (2, 3),
+        zones: &[14],
+        regions: &[],
+    },
+    Country {
+        name: "European Russia",
+        share: 6.0,
+        prefixes: &["UA", "RA", "RN", "RU", "RW", "R"],
+        digits: "1346",
+        suffix: (1, 3),
+        zones: &[16],
+        regions: &[],
+    },
+    Country {
+        name: "Spain",
+        share: 5.0,
+        prefixes: &["EA", "EB", "EC"],
+        digits: "1234567",
+        suffix: (1, 3),
+        zones: &[14],
+        regions: &[],
+    },
+    Country {
+        name: "France",
+        share: 4.0,
+        prefixes: &["F"],
+        digits: "1245689",
+        suffix: (2, 3),
+        zones: &[14],
+        regions: &[],
+    },
+    Country {
+        name: "Netherlands",
+        share: 3.0,
+        prefixes: &["PA", "PD", "PE", "PH"],
+        digits: "0123456789",
+        suffix: (1, 3),
+        zones: &[14],
+        regions: &[],
+    },
+    Country {
+        name: "Sweden",
+        share: 3.0,
+        prefixes: &["SM", "SA"],
+        digits: "01234567",
+        suffix: (1, 3),
+        zones: &[14],
+        regions: &[],
+    },
+    Country {
+        name: "Finland",
+        share: 3.0,
+        prefixes: &["OH"],
+        digits: "0123456789",
+        suffix: (1, 3),
+        zones: &[15],
+        regions: &[],
+    },
+    Country {
+        name: "Brazil",
+        share: 3.0,
+        prefixes: &["PY", "PP", "PU"],
+        digits: "123456789",
+        suffix: (2, 3),
+        zones: &[11],
+        regions: &[],
+    },
+    Country {
+        name: "Australia",
+        share: 3.0,
+        prefixes: &["VK"],
+        digits: "12345678",
+        suffix: (2, 3),
+        zones: &[30, 30, 30, 30, 30, 29, 30, 29],
+        regions: &[],
+    },
+    Country {
+        name: "Argentina",
+        share: 2.0,
+        prefixes: &["LU"],
+        digits: "123456789",
+        suffix: (2, 3),
+        zones: &[13],
+        regions: &[],
+    },
+    Country {
+        name: "New Zealand",
+        share: 1.0,
+        prefixes: &["ZL"],
+        digits: "1234",
+        suffix: (2, 3),
+        zones: &[32],
+        regions: &[],
+    },
+    Country {
+        name: "South Africa",
+        share: 1.0,
+        prefixes: &["ZS"],
+        digits: "123456",
+        suffix: (2, 3),
+        zones: &[38],
+        regions: &[],
+    },
+];
+
+/// The highest serial number a station sends: it has made at most this many
+/// contacts so far.
+const MAX_SERIAL: usize = 300;
+
+/// The power DX stations send in place of a state.
+const POWERS: &[&str] = &["100", "500", "KW", "1K", "5"];
+
+/// What stations send after the signal report.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Exchange {
+    /// A serial number counting the contacts made, such as `5NN 123`.
+    #[default]
+    Serial,
+    /// The station's CQ zone, such as `5NN 14`.
+    Zone,
+    /// The state or province of North American stations, such as `5NN MA`.
+    /// Stations elsewhere send their power, as in the ARRL DX contest.
+    State,
+}
+
+impl Exchange {
+    pub const ALL: [Self; 3] = [Self::Serial, Self::Zone, Self::State];
+
+    /// The name to show in a user interface.
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Serial => "Serial Number",
+            Self::Zone => "CQ Zone",
+            Self::State => "State or Province",
+        }
+    }
+}
+
+/// A station calling in a contest.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Station {
+    pub callsign: String,
+    pub country: &'static str,
+    pub zone: u8,
+    /// The state or province, for stations in the United States and Canada.
+    pub region: Option<&'static str>,
+    /// The serial number the station sends when worked.
+    pub serial: u32,
+}
+
+/// How the callers of a pile-up are spread out.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PileUp {
+    /// The number of stations calling at once.
+    pub callers: usize,
+    /// The slowest speed callers send at.
+    pub min_wpm: u32,
+    /// The fastest speed callers send at.
+    pub max_wpm: u32,
+    /// The pitch callers are centred on, in hertz.
+    pub pitch: f32,
+    /// How far apart the highest and lowest callers can be, in hertz.
+    pub spread: f32,
+    /// The latest a caller starts after the first, so calls overlap without
+    /// all starting together.
+    pub stagger: Duration,
+}
+
+impl Default for PileUp {
+    /// Four callers between 22 and 32 WPM, within 400 Hz around 700 Hz and
+    /// starting up to 600 ms apart.
+    fn default() -> Self {
+        Self {
+            callers: 4,
+            min_wpm: 22,
+            max_wpm: 32,
+            pitch: 700.0,
+            spread: 400.0,
+            stagger: Duration::from_millis(600),
+        }
+    }
+}
+
+/// A station as heard on the band, with its own pitch, speed and strength.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Caller {
+    pub station: Station,
+    pub tone: Tone,
+    pub timing: Timing,
+    /// Time from the start of the pile-up until the caller starts sending.
+    pub delay: Duration,
+    /// Signal strength relative to full scale, from `0.0` to `1.0`.
+    pub level: f32,
+}
+
+impl Caller {
+    /// The caller sending `text`, ready to [`mix`] with other callers.
+    #[must_use]
+    pub fn transmission(&self, text: &str, options: &Options) -> Transmission {
+        Transmission {
+            morse_code: encode(text, options),
+            tone: self.tone,
+            timing: self.timing,
+            delay: self.delay,
+            level: self.level,
+        }
+    }
+}
+
+/// Mixes `callers` each sending their callsign into one buffer of mono
+/// samples at `sample_rate`, ready for
+/// [`Backend::play_samples`](crate::backend::Backend::play_samples).
+#[must_use]
+pub fn pile_up_samples(callers: &[Caller], options: &Options, sample_rate: u32) -> Vec<f32> {
+    let transmissions: Vec<Transmission> = callers
+        .iter()
+        .map(|caller| caller.transmission(&caller.station.callsign, options))
+        .collect();
+    mix(&transmissions, options, sample_rate)
+}
+
+/// Generates the stations and exchanges heard in a contest.
+#[derive(Debug, Clone)]
+pub struct Contest {
+    pub exchange: Exchange,
+    /// Whether numbers are sent with the usual contest abbreviations, `N`
+    /// for 9 and `T` for 0, as in `5NN 1T3`.
+    pub cut_numbers: bool,
+    random: Random,
+}
+
+impl Contest {
+    /// A contest with the given exchange and cut numbers. The same `seed`
+    /// gives the same stations.
+    #[must_use]
+    pub fn new(exchange: Exchange, seed: u64) -> Self {
+        Self {
+            exchange,
+            cut_numbers: true,
+            random: Random::new(seed),
+        }
+    }
+
+    /// A station from a country picked by how often it is heard, with a
+    /// callsign following that country's prefix rules and its own low serial
+    /// number.
+    pub fn station(&mut self) -> Station {
+        const LETTERS: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+        let shares: Vec<f64> = COUNTRIES.iter().map(|country| country.share).collect();
+        let country = &COUNTRIES[self.random.weighted(&shares)];
+
+        let digit = self.random.below(country.digits.len());
+        let (shortest, longest) = country.suffix;
+        let suffix = shortest + self.random.below(longest - shortest + 1);
+
+        let mut callsign = self.random.choose(country.prefixes).to_string();
+        callsign.push(char::from(country.digits.as_bytes()[digit]));
+        callsign.extend((0..suffix).map(|_| char::from(LETTERS.as_bytes()[self.random.below(26)])));
+
+        let region = country
+            .regions
+            .get(digit)
+            .map(|regions| self.random.choose(regions));
+        Station {
+            callsign,
+            country: country.name,
+            zone: country.zone(digit),
+            region,
+            serial: 1 + self.random.below(MAX_SERIAL) as u32,
+        }
+    }
+
+    /// What `station` sends after being worked: a signal report followed by
+    /// the contest's exchange.
+    pub fn exchange(&mut self, station: &Station) -> String {
+        let info = match self.exchange {
+            Exchange::Serial => self.cut(&station.serial.to_string()),
+            Exchange::Zone => self.cut(&format!("{:02}", station.zone)),
+            Exchange::State => match station.region {
+                Some(region) => region.to_string(),
+                None => {
+                    let power = self.random.choose(POWERS);
+                    self.cut(power)
+                }
+            },
+        };
+        format!("{} {info}", self.cut("599"))
+    }
+
+    /// A station heard at a random pitch and speed within `pile_up`,
+    /// starting straight away at full strength.
+    pub fn caller(&mut self, pile_up: &PileUp) -> Caller {
+        let min_wpm = pile_up.min_wpm.min(pile_up.max_wpm);
+        let speeds = (pile_up.max_wpm - min_wpm) as usize + 1;
+        let wpm = min_wpm + self.random.below(speeds) as u32;
+        let offset = (self.random.fraction() as f32 - 0.5) * pile_up.spread;
+
+        Caller {
+            station: self.station(),
+            tone: Tone::new(pile_up.pitch + offset),
+            timing: Timing::from_wpm(wpm),
+            delay: Duration::ZERO,
+            level: 1.0,
+        }
+    }
+
+    /// The stations calling at once in a pile-up, each at its own pitch,
+    /// speed and strength and starting at its own time.
+    pub fn pile_up(&mut self, pile_up: &PileUp) -> Vec<Caller> {
+        (0..pile_up.callers)
+            .map(|_| {
+                let mut caller = self.caller(pile_up);
+                caller.delay = pile_up.stagger.mul_f64(self.random.fraction());
+                caller.level = 0.3 + 0.7 * self.random.fraction() as f32;
+                caller
+            })
+            .collect()
+    }
+
+    /// `number` with 9 and 0 cut to `N` and `T` if cut numbers are on.
+    fn cut(&self, number: &str) -> String {
+        if self.cut_numbers {
+            number.replace('9', "N").replace('0', "T")
+        } else {
+            number.to_string()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stations_send_their_own_low_serial() {
+        let mut contest = Contest::new(Exchange::Serial, 7);
+        contest.cut_numbers = false;
+        for _ in 0..200 {
+            let station = contest.station();
+            assert!((1..=MAX_SERIAL as u32).contains(&station.serial));
+            assert_eq!(
+                contest.exchange(&station),
+                format!("599 {}", station.serial)
+            );
+        }
+    }
+
+    #[test]
+    fn serials_are_sent_with_cut_numbers() {
+        let mut contest = Contest::new(Exchange::Serial, 7);
+        let mut station = contest.station();
+        station.serial = 109;
+        assert_eq!(contest.exchange(&station), "5NN 1TN");
+
+        contest.cut_numbers = false;
+        assert_eq!(contest.exchange(&station), "599 109");
+    }
+}
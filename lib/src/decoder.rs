@@ -0,0 +1,616 @@
+use std::{collections::VecDeque, f32::consts::TAU, io::Read, path::Path, time::Duration};
+
+use hound::{SampleFormat, WavReader};
+use morsify::{MorseCode, Options};
+
+use crate::{Result, timing::Timing};
+
+/// Length of one detector block. Shorter blocks follow fast keying more
+/// closely; longer blocks reject more noise.
+const BLOCK_DURATION: Duration = Duration::from_millis(5);
+
+/// Number of blocks each tone measurement spans. Measurements overlap, so
+/// the key state is still updated once per block, but the wider window
+/// narrows the detector bandwidth and rejects more noise.
+const WINDOW_BLOCKS: usize = 2;
+
+/// Number of consecutive blocks that must disagree with the key state before
+/// it flips, so single noisy blocks do not split or create elements.
+const DEBOUNCE_BLOCKS: usize = 2;
+
+/// Number of recent mark and gap lengths used to estimate the speed.
+const HISTORY: usize = 32;
+
+/// Smallest ratio between two mark lengths for them to count as a dit and a
+/// dah rather than sloppy keying of the same element.
+const DIT_DAH_RATIO: f32 = 1.8;
+
+/// Smallest ratio between two gap lengths for them to count as a character
+/// gap and a word gap.
+const CHARACTER_WORD_RATIO: f32 = 1.6;
+
+/// Minimum ratio between the tone peak and the noise floor for a block to
+/// count as key-down.
+const MIN_SIGNAL_TO_NOISE: f32 = 3.0;
+
+/// Marks shorter than this fraction of a dit are treated as noise.
+const GLITCH_FRACTION: f32 = 0.3;
+
+/// How quickly the noise floor follows key-up blocks, and the peak decays,
+/// per block. The peak jumps at once to a louder block.
+const FLOOR_SMOOTHING: f32 = 0.05;
+const PEAK_DECAY: f32 = 0.0005;
+
+/// Range scanned when the tone frequency is not given.
+const MIN_FREQUENCY: f32 = 200.0;
+const MAX_FREQUENCY: f32 = 2000.0;
+
+/// A decoded character or word boundary, produced as soon as the silence after
+/// it is long enough to be sure it has ended.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Event {
+    /// A character, with its dots and dashes written as `.` and `-`, and its
+    /// text as decoded by `morsify`.
+    Character { pattern: String, text: String },
+    /// The silence after the last character is long enough to end a word.
+    WordGap,
+}
+
+/// The result of decoding a complete recording.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Decoded {
+    /// The Morse code, written with the characters of the [`Options`] passed
+    /// to the decoder.
+    pub morse: String,
+    pub text: String,
+    /// Tone frequency in hertz that was listened to.
+    pub frequency: f32,
+    /// Estimated sending speed, from the measured dit length.
+    pub wpm: f32,
+}
+
+/// Single-bin DFT that measures the strength of one frequency over a block.
+#[derive(Debug, Clone, Copy)]
+struct Goertzel {
+    coefficient: f32,
+}
+
+impl Goertzel {
+    fn new(frequency: f32, sample_rate: u32) -> Self {
+        Self {
+            coefficient: 2.0 * (TAU * frequency / sample_rate as f32).cos(),
+        }
+    }
+
+    /// Amplitude of the frequency in `samples`, normalized so a full-scale
+    /// sine gives roughly `1.0` whatever the block length.
+    fn magnitude(self, samples: &[f32]) -> f32 {
+        let (mut s1, mut s2) = (0.0_f32, 0.0_f32);
+        for &sample in samples {
+            let s0 = sample + self.coefficient * s1 - s2;
+            s2 = s1;
+            s1 = s0;
+        }
+        let power = (s1 * s1 + s2 * s2 - self.coefficient * s1 * s2).max(0.0);
+        2.0 * power.sqrt() / samples.len().max(1) as f32
+    }
+}
+
+/// Streaming CW decoder for a tone at a known frequency.
+///
+/// Samples are pushed in as they arrive; decoded characters come out once the
+/// gap after them is complete. The key threshold follows the signal and noise
+/// levels, and the dit length is re-estimated from the recent marks, so the
+/// decoder adapts to both the signal strength and the sending speed.
+#[derive(Debug, Clone)]
+pub struct Decoder {
+    sample_rate: u32,
+    frequency: f32,
+    goertzel: Goertzel,
+    block_len: usize,
+    block: Vec<f32>,
+    floor: f32,
+    peak: f32,
+    key_down: bool,
+    run: usize,
+    pending: usize,
+    marks: VecDeque<f32>,
+    dit: f32,
+    gaps: VecDeque<f32>,
+    gap_unit: f32,
+    pattern: String,
+    word_open: bool,
+    events: Vec<Event>,
+}
+
+impl Decoder {
+    /// A decoder for mono `sample_rate` audio, listening at `frequency` hertz.
+    #[must_use]
+    pub fn new(sample_rate: u32, frequency: f32) -> Self {
+        let sample_rate = sample_rate.max(1);
+        let block_len = ((sample_rate as f32 * BLOCK_DURATION.as_secs_f32()) as usize).max(16);
+
+        Self {
+            sample_rate,
+            frequency,
+            goertzel: Goertzel::new(frequency, sample_rate),
+            block_len,
+            block: Vec::with_capacity(WINDOW_BLOCKS * block_len),
+            floor: f32::INFINITY,
+            peak: 0.0,
+            key_down: false,
+            run: 0,
+            pending: 0,
+            marks: VecDeque::with_capacity(HISTORY),
+            dit: Timing::default().dit().as_secs_f32(),
+            gaps: VecDeque::with_capacity(HISTORY),
+            gap_unit: Timing::default().dit().as_secs_f32(),
+            pattern: String::new(),
+            word_open: false,
+            events: Vec::new(),
+        }
+    }
+
+    #[must_use]
+    pub const fn frequency(&self) -> f32 {
+        self.frequency
+    }
+
+    /// Current estimate of the sending speed in words per minute.
+    #[must_use]
+    pub fn wpm(&self) -> f32 {
+        1.2 / self.dit
+    }
+
+    /// Current signal-to-noise ratio of the tone in decibels, from the tracked
+    /// peak and noise floor.
+    #[must_use]
+    pub fn signal_strength(&self) -> f32 {
+        20.0 * (self.peak / self.floor.max(f32::EPSILON)).log10()
+    }
+
+    /// Feeds mono samples to the decoder and returns the events they
+    /// completed.
+    pub fn push(&mut self, samples: &[f32]) -> Vec<Event> {
+        for &sample in samples {
+            self.block.push(sample);
+            if self.block.len() == WINDOW_BLOCKS * self.block_len {
+                let magnitude = self.goertzel.magnitude(&self.block);
+                self.block.drain(..self.block_len);
+                self.process_block(magnitude);
+            }
+        }
+
+        std::mem::take(&mut self.events)
+    }
+
+    /// Ends the stream, returning the events for a character or mark still in
+    /// progress.
+    pub fn finish(&mut self) -> Vec<Event> {
+        self.run += self.pending;
+        self.pending = 0;
+        if self.key_down {
+            self.end_mark();
+            self.key_down = false;
+        }
+        self.end_character();
+
+        std::mem::take(&mut self.events)
+    }
+
+    fn block_seconds(&self, blocks: usize) -> f32 {
+        blocks as f32 * self.block_len as f32 / self.sample_rate as f32
+    }
+
+    fn process_block(&mut self, magnitude: f32) {
+        if self.floor.is_infinite() {
+            self.floor = magnitude;
+        }
+        if magnitude > self.peak {
+            self.peak = magnitude;
+        } else {
+            self.peak += (self.floor - self.peak) * PEAK_DECAY;
+        }
+
+        let span = self.peak - self.floor;
+        let on_threshold = self.floor + span * 0.55;
+        let off_threshold = self.floor + span * 0.45;
+        let threshold = if self.key_down {
+            off_threshold
+        } else {
+            on_threshold
+        };
+        let is_on = self.peak > self.floor * MIN_SIGNAL_TO_NOISE && magnitude > threshold;
+
+        if !self.key_down && magnitude < on_threshold {
+            self.floor += (magnitude - self.floor) * FLOOR_SMOOTHING;
+        }
+
+        if is_on == self.key_down {
+            self.run += 1 + self.pending;
+            self.pending = 0;
+        } else {
+            self.pending += 1;
+            if self.pending >= DEBOUNCE_BLOCKS {
+                if self.key_down {
+                    self.end_mark();
+                } else {
+                    self.end_gap();
+                    self.word_open = true;
+                }
+                self.key_down = is_on;
+                self.run = self.pending;
+                self.pending = 0;
+            }
+        }
+
+        if !self.key_down {
+            let silence = self.block_seconds(self.run + self.pending);
+            if silence >= 2.0 * self.dit {
+                self.end_character();
+            }
+            if silence >= 5.0 * self.gap_unit && self.word_open && self.pattern.is_empty() {
+                self.word_open = false;
+                self.events.push(Event::WordGap);
+            }
+        }
+    }
+
+    fn end_mark(&mut self) {
+        let length = self.block_seconds(self.run);
+        if length < GLITCH_FRACTION * self.dit {
+            return;
+        }
+
+        if self.marks.len() == HISTORY {
+            self.marks.pop_front();
+        }
+        self.marks.push_back(length);
+        self.dit = estimate_unit(
+            self.marks.make_contiguous(),
+            self.dit,
+            (1.0, 3.0),
+            DIT_DAH_RATIO,
+        );
+
+        self.pattern
+            .push(if length < 2.0 * self.dit { '.' } else { '-' });
+    }
+
+    /// Records the length of a gap that ended a character or word, which may
+    /// be stretched beyond the dit length by Farnsworth timing.
+    fn end_gap(&mut self) {
+        let length = self.block_seconds(self.run);
+        if length < 2.0 * self.dit {
+            return;
+        }
+
+        if self.gaps.len() == HISTORY {
+            self.gaps.pop_front();
+        }
+        self.gaps.push_back(length);
+
+        // Gaps recorded while the dit estimate was still settling may be
+        // element gaps; only the ones that are still long enough count.
+        let gaps: Vec<f32> = self
+            .gaps
+            .iter()
+            .copied()
+            .filter(|gap| *gap >= 2.0 * self.dit)
+            .collect();
+        self.gap_unit =
+            estimate_unit(&gaps, self.gap_unit, (3.0, 7.0), CHARACTER_WORD_RATIO).max(self.dit);
+    }
+
+    fn end_character(&mut self) {
+        if self.pattern.is_empty() {
+            return;
+        }
+
+        let pattern = std::mem::take(&mut self.pattern);
+        let text = MorseCode::default().decode(&pattern);
+        self.events.push(Event::Character { pattern, text });
+    }
+}
+
+/// Estimates a timing unit from lengths that are either `units.0` or
+/// `units.1` units long, such as dits and dahs.
+///
+/// The sorted lengths are split into a short and a long group between the
+/// neighbours whose ratio is closest to `units.1 / units.0`, ignoring ratios
+/// below `min_ratio`. Each group's median gives the unit, so a few stray
+/// lengths do not pull the estimate. If no ratio qualifies, all lengths are
+/// assumed to be in the group closer to `current`.
+fn estimate_unit(lengths: &[f32], current: f32, units: (f32, f32), min_ratio: f32) -> f32 {
+    let (short_units, long_units) = units;
+    let expected_ratio = long_units / short_units;
+    let mut sorted = lengths.to_vec();
+    sorted.sort_by(f32::total_cmp);
+
+    let split = sorted
+        .windows(2)
+        .enumerate()
+        .map(|(i, pair)| (i + 1, pair[1] / pair[0].max(f32::EPSILON)))
+        .filter(|(_, ratio)| *ratio >= min_ratio)
+        .min_by(|a, b| {
+            (a.1.ln() - expected_ratio.ln())
+                .abs()
+                .total_cmp(&(b.1.ln() - expected_ratio.ln()).abs())
+        });
+
+    match split {
+        Some((split, _)) => {
+            let (short, long) = sorted.split_at(split);
+            (median(short) / short_units * short.len() as f32
+                + median(long) / long_units * long.len() as f32)
+                / sorted.len() as f32
+        }
+        None if sorted.is_empty() => current,
+        None => {
+            let median = median(&sorted);
+            if median < (short_units + long_units) / 2.0 * current {
+                median / short_units
+            } else {
+                median / long_units
+            }
+        }
+    }
+}
+
+/// Median of sorted, non-empty lengths.
+fn median(sorted: &[f32]) -> f32 {
+    sorted[sorted.len() / 2]
+}
+
+/// Finds the strongest tone between 200 Hz and 2 kHz.
+#[must_use]
+pub fn detect_frequency(samples: &[f32], sample_rate: u32) -> f32 {
+    const BLOCK_LEN: usize = 2048;
+    const MAX_BLOCKS: usize = 200;
+
+    let blocks: Vec<&[f32]> = samples.chunks(BLOCK_LEN).collect();
+    let stride = blocks.len().div_ceil(MAX_BLOCKS).max(1);
+    let power = |frequency: f32| {
+        let goertzel = Goertzel::new(frequency, sample_rate);
+        blocks
+            .iter()
+            .step_by(stride)
+            .map(|block| goertzel.magnitude(block).powi(2))
+            .sum::<f32>()
+    };
+    let strongest = |frequencies: &mut dyn Iterator<Item = f32>| {
+        frequencies
+            .map(|frequency| (frequency, power(frequency)))
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+            .map_or(MIN_FREQUENCY, |(frequency, _)| frequency)
+    };
+
+    let coarse = strongest(
+        &mut (0..)
+            .map(|step| MIN_FREQUENCY + step as f32 * 10.0)
+            .take_while(|frequency| *frequency <= MAX_FREQUENCY),
+    );
+    strongest(&mut (-10..=10).map(|offset| coarse + offset as f32))
+}
+
+/// Decodes a complete mono recording.
+///
+/// If `frequency` is `None` the tone is located with [`detect_frequency`].
+/// The recording is decoded twice, the second time starting from the levels
+/// and speed measured over the whole of the first pass, so the first
+/// characters are not lost while the decoder settles.
+#[must_use]
+pub fn decode_samples(
+    samples: &[f32],
+    sample_rate: u32,
+    frequency: Option<f32>,
+    options: &Options,
+) -> Decoded {
+    let frequency = frequency.unwrap_or_else(|| detect_frequency(samples, sample_rate));
+
+    let mut first_pass = Decoder::new(sample_rate, frequency);
+    first_pass.push(samples);
+    first_pass.finish();
+
+    let mut decoder = Decoder::new(sample_rate, frequency);
+    decoder.floor = first_pass.floor;
+    decoder.peak = first_pass.peak;
+    decoder.marks.clone_from(&first_pass.marks);
+    decoder.dit = first_pass.dit;
+    decoder.gaps.clone_from(&first_pass.gaps);
+    decoder.gap_unit = first_pass.gap_unit;
+    let mut events = decoder.push(samples);
+    events.extend(decoder.finish());
+
+    Decoded {
+        morse: events_to_morse(&events, options),
+        text: events_to_text(&events),
+        frequency,
+        wpm: decoder.wpm(),
+    }
+}
+
+/// Decodes a WAV recording. Multi-channel files are mixed down to mono.
+///
+/// # Errors
+///
+/// Returns [`Error::Wav`] if the file cannot be opened or is not a valid WAV
+/// file.
+///
+/// [`Error::Wav`]: crate::Error::Wav
+pub fn decode_wav(
+    path: impl AsRef<Path>,
+    frequency: Option<f32>,
+    options: &Options,
+) -> Result<Decoded> {
+    let (samples, sample_rate) = read_wav(WavReader::open(path)?)?;
+    Ok(decode_samples(&samples, sample_rate, frequency, options))
+}
+
+/// Decodes a WAV recording read from `reader`, such as standard input, like
+/// [`decode_wav`].
+///
+/// # Errors
+///
+/// Returns [`Error::Wav`] if the stream cannot be read or is not valid WAV.
+///
+/// [`Error::Wav`]: crate::Error::Wav
+pub fn decode_wav_from(
+    reader: impl Read,
+    frequency: Option<f32>,
+    options: &Options,
+) -> Result<Decoded> {
+    let (samples, sample_rate) = read_wav(WavReader::new(reader)?)?;
+    Ok(decode_samples(&samples, sample_rate, frequency, options))
+}
+
+fn read_wav<R: Read>(reader: WavReader<R>) -> hound::Result<(Vec<f32>, u32)> {
+    let spec = reader.spec();
+    let samples: Vec<f32> = match spec.sample_format {
+        SampleFormat::Float => reader.into_samples::<f32>().collect::<Result<_, _>>()?,
+        SampleFormat::Int => {
+            let scale = (1_i64 << (spec.bits_per_sample - 1)) as f32;
+            reader
+                .into_samples::<i32>()
+                .map(|sample| sample.map(|sample| sample as f32 / scale))
+                .collect::<Result<_, _>>()?
+        }
+    };
+
+    let channels = usize::from(spec.channels.max(1));
+    let mono = samples
+        .chunks(channels)
+        .map(|frame| frame.iter().sum::<f32>() / channels as f32)
+        .collect();
+
+    Ok((mono, spec.sample_rate))
+}
+
+/// Writes decoder events as Morse code in the `options` alphabet, with the
+/// separator between characters and a spaced-out word marker between words.
+#[must_use]
+pub fn events_to_morse(events: &[Event], options: &Options) -> String {
+    let mut morse = String::new();
+    let mut word_gap = false;
+    for event in events {
+        match event {
+            Event::Character { pattern, .. } => {
+                if !morse.is_empty() {
+                    morse.push(options.separator);
+                    if word_gap {
+                        morse.push(options.space);
+                        morse.push(options.separator);
+                    }
+                }
+                morse.extend(pattern.chars().map(|symbol| {
+                    if symbol == '.' {
+                        options.dot
+                    } else {
+                        options.dash
+                    }
+                }));
+                word_gap = false;
+            }
+            Event::WordGap => word_gap = true,
+        }
+    }
+    morse
+}
+
+/// Writes decoder events as text, with a space between words.
+#[must_use]
+pub fn events_to_text(events: &[Event]) -> String {
+    let mut text = String::new();
+    let mut word_gap = false;
+    for event in events {
+        match event {
+            Event::Character {
+                text: character, ..
+            } => {
+                if word_gap && !text.is_empty() {
+                    text.push(' ');
+                }
+                text.push_str(character);
+                word_gap = false;
+            }
+            Event::WordGap => word_gap = true,
+        }
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{render::synthesize, tone::Tone};
+
+    const SAMPLE_RATE: u32 = 8000;
+
+    fn options() -> Options {
+        Options {
+            dot: '.',
+            dash: '-',
+            separator: ' ',
+            space: '/',
+            ..Options::default()
+        }
+    }
+
+    fn samples(morse: &str, frequency: f32, wpm: u32) -> Vec<f32> {
+        synthesize(
+            morse,
+            &options(),
+            Tone::new(frequency),
+            Timing::from_wpm(wpm),
+            SAMPLE_RATE,
+        )
+        .collect()
+    }
+
+    #[test]
+    fn decodes_synthesized_morse() {
+        let morse = "-.-. --.- / -.. .";
+        let decoded = decode_samples(
+            &samples(morse, 700.0, 20),
+            SAMPLE_RATE,
+            Some(700.0),
+            &options(),
+        );
+
+        assert_eq!(decoded.morse, morse);
+        assert!((decoded.wpm - 20.0).abs() < 2.0, "{}", decoded.wpm);
+    }
+
+    #[test]
+    fn finds_the_tone_frequency() {
+        let morse = ".--. .- .-. .. ... / .--. .- .-. .. ...";
+        let decoded = decode_samples(&samples(morse, 850.0, 25), SAMPLE_RATE, None, &options());
+
+        assert!(
+            (decoded.frequency - 850.0).abs() <= 10.0,
+            "{}",
+            decoded.frequency
+        );
+        assert_eq!(decoded.morse, morse);
+        assert!((decoded.wpm - 25.0).abs() < 2.5, "{}", decoded.wpm);
+    }
+
+    #[test]
+    fn text_has_a_space_between_words() {
+        let character = |text: &str| Event::Character {
+            pattern: ".".to_string(),
+            text: text.to_string(),
+        };
+        let events = [
+            Event::WordGap,
+            character("E"),
+            character("E"),
+            Event::WordGap,
+            character("E"),
+            Event::WordGap,
+        ];
+
+        assert_eq!(events_to_text(&events), "EE E");
+        assert_eq!(events_to_morse(&events, &options()), ". . / .");
+    }
+}
@@ -1,65 +1,380 @@
 use std::{
-    thread::{sleep, spawn},
+    sync::{
+        Arc, Condvar, Mutex,
+        mpsc::{self, Receiver},
+    },
+    thread::{JoinHandle, spawn},
     time::Duration,
 };
 
+use cpal::traits::{DeviceTrait, HostTrait};
 use morsify::Options;
-use rodio::{OutputStream, Source};
+use rodio::{
+    ChannelCount, OutputStream, OutputStreamBuilder, SampleRate, Sink, Source,
+    buffer::SamplesBuffer,
+};
+
+use crate::{
+    Error, Result,
+    playback::PlaybackOutcome,
+    progress::{Progress, schedule},
+    timeline::timeline,
+    timing::Timing,
+    tone::{Synth, Tone},
+};
+
+#[cfg(target_os = "linux")]
+mod clipboard;
+mod input;
+mod platform;
+mod settings;
+#[cfg(target_os = "linux")]
+mod speech;
+
+#[cfg(target_os = "linux")]
+pub use self::clipboard::{
+    Selection, copy_to_clipboard, copy_to_selection, read_from_clipboard, read_from_selection,
+};
+pub use self::input::{ListenHandle, ListenUpdate, listen};
+pub use self::platform::Platform;
+pub use self::settings::{read_setting, write_setting};
+#[cfg(target_os = "linux")]
+pub use self::speech::{Voice, play_text, play_text_with_voice, stop_text, voices};
+
+/// How often the playback thread checks the sink when no control request arrives.
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    Playing,
+    Paused,
+    Stopped,
+}
+
+#[derive(Debug)]
+struct Control {
+    state: Mutex<State>,
+    changed: Condvar,
+}
+
+impl Control {
+    fn new() -> Self {
+        Self {
+            state: Mutex::new(State::Playing),
+            changed: Condvar::new(),
+        }
+    }
+
+    fn set(&self, state: State) {
+        let mut current = self.state.lock().unwrap();
+        if *current != State::Stopped {
+            *current = state;
+        }
+        self.changed.notify_all();
+    }
+
+    fn get(&self) -> State {
+        *self.state.lock().unwrap()
+    }
+
+    fn wait(&self, timeout: Duration) -> State {
+        let state = self.state.lock().unwrap();
+        let (state, _) = self.changed.wait_timeout(state, timeout).unwrap();
+        *state
+    }
+}
+
+/// A handle to Morse code playing on a background thread.
+///
+/// Dropping the handle does not stop playback; call [`PlaybackHandle::stop`]
+/// to silence it early.
+#[derive(Debug)]
+pub struct PlaybackHandle {
+    control: Arc<Control>,
+    thread: JoinHandle<()>,
+}
+
+impl PlaybackHandle {
+    /// Stops playback immediately. A stopped playback cannot be resumed.
+    pub fn stop(&self) {
+        self.control.set(State::Stopped);
+    }
+
+    /// Pauses playback, including any tone that is currently sounding.
+    pub fn pause(&self) {
+        self.control.set(State::Paused);
+    }
+
+    /// Resumes playback after [`PlaybackHandle::pause`].
+    pub fn resume(&self) {
+        self.control.set(State::Playing);
+    }
+
+    /// Returns `true` once the playback thread has exited, either because the
+    /// message finished or because it was stopped.
+    #[must_use]
+    pub fn is_finished(&self) -> bool {
+        self.thread.is_finished()
+    }
+
+    /// Blocks the current thread until playback has ended.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the playback thread panicked.
+    pub fn join(self) {
+        self.thread.join().unwrap();
+    }
+}
+
+/// The audio device and stream format used by [`play_morse`].
+///
+/// Fields left as `None` take the device's own default. The tone is mono and
+/// sounds on every channel.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct AudioOutput {
+    /// Name of the output device, as returned by [`output_devices`]. `None`
+    /// uses the system default device.
+    pub device: Option<String>,
+    /// Sample rate in hertz.
+    pub sample_rate: Option<u32>,
+    /// Number of output channels.
+    pub channels: Option<u16>,
+}
+
+impl AudioOutput {
+    fn open(&self) -> Result<OutputStream> {
+        let mut stream = self.open_stream()?;
+        // rodio reports dropped streams on stderr, which belongs to the
+        // caller.
+        stream.log_on_drop(false);
+        Ok(stream)
+    }
+
+    fn open_stream(&self) -> Result<OutputStream> {
+        // Without any preference, let rodio fall back to another device or
+        // configuration if the default one cannot be opened.
+        if *self == Self::default() {
+            return Ok(OutputStreamBuilder::open_default_stream()?);
+        }
+
+        let host = cpal::default_host();
+        let device = match &self.device {
+            Some(name) => host
+                .output_devices()?
+                .find(|device| device.name().is_ok_and(|device_name| device_name == *name))
+                .ok_or_else(|| Error::Audio(format!("no audio output device named {name:?}")))?,
+            None => host
+                .default_output_device()
+                .ok_or_else(|| Error::Audio("no audio output device is available".to_string()))?,
+        };
+
+        let mut builder = OutputStreamBuilder::from_device(device)?;
+        if let Some(sample_rate) = self.sample_rate {
+            builder = builder.with_sample_rate(sample_rate);
+        }
+        if let Some(channels) = self.channels {
+            builder = builder.with_channels(channels);
+        }
+        Ok(builder.open_stream()?)
+    }
+}
+
+/// Waits for a background thread to report whether it could open its audio
+/// stream, handing back the thread if it did.
+fn wait_for_stream<T>(
+    opened: &Receiver<Result<()>>,
+    thread: JoinHandle<T>,
+) -> Result<JoinHandle<T>> {
+    match opened.recv() {
+        Ok(result) => result.map(|()| thread),
+        // The thread only hangs up without reporting if it panicked.
+        Err(_) => match thread.join() {
+            Ok(_) => Err(Error::Audio(
+                "the audio thread exited unexpectedly".to_string(),
+            )),
+            Err(panic) => std::panic::resume_unwind(panic),
+        },
+    }
+}
+
+/// Lists the names of the audio output devices [`AudioOutput::device`] can
+/// select.
+///
+/// # Errors
+///
+/// Returns an error if the audio host cannot enumerate its devices.
+pub fn output_devices() -> Result<Vec<String>> {
+    Ok(cpal::default_host()
+        .output_devices()?
+        .filter_map(|device| device.name().ok())
+        .collect())
+}
+
+/// Adapts a [`Synth`] to rodio's mono [`Source`].
+struct SynthSource(Synth);
+
+impl Iterator for SynthSource {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        self.0.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl Source for SynthSource {
+    fn current_span_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> ChannelCount {
+        1
+    }
+
+    fn sample_rate(&self) -> SampleRate {
+        self.0.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+}
 
 /// Plays a given Morse code string using audio synthesis.
 ///
-/// # Panics
+/// Playback happens on a background thread; the returned [`PlaybackHandle`]
+/// can stop, pause or resume it. `onend` is called from that thread once the
+/// last element has sounded or playback was stopped. The tone is synthesized
+/// at the sample rate of the opened stream.
 ///
-/// This function may panic if:
-/// - `OutputStream::try_default()` fails to initialize the default audio output stream. This could happen if there are issues with the audio subsystem or hardware.
-/// - `create_sine_wave(frequency, duration_ms)` might panic if it fails to create a valid sine wave buffer. This is generally unlikely but can occur if there's an issue with the buffer size or sample rate calculations.
-/// - `stream_handle.play_raw(sine_wave.convert_samples())` might panic if the audio playback fails. This can occur if there's an issue with the audio stream or if the `convert_samples` method encounters an error.
-/// - `sleep(Duration::from_millis(u64::from(time_unit)))` might cause a panic if `u64::from(time_unit)` is out of bounds for `Duration::from_millis`. This is highly unlikely given that `u32` values are within the acceptable range.
+/// # Errors
+///
+/// Returns [`Error::Audio`] if the audio output cannot be opened.
 pub fn play_morse(
     morse_code: &str,
     options: Options,
-    frequency: f32,
-    time_unit: u32,
-    mut onend: impl FnMut() + 'static,
-) {
-    let morse_code = morse_code.to_string();
-
-    spawn(move || {
-        let (_stream, stream_handle) = OutputStream::try_default().unwrap();
-        let create_sine_wave = |frequency: f32, duration_ms: u32| {
-            let sample_rate = 44100;
-            let samples = (sample_rate as f32 * (duration_ms as f32 / 1000.0)) as usize;
-            let mut buffer = vec![0.0; samples];
-            for (i, item) in buffer.iter_mut().enumerate().take(samples) {
-                let t = i as f32 / sample_rate as f32;
-                *item = (2.0 * std::f32::consts::PI * frequency * t).sin();
+    tone: Tone,
+    timing: Timing,
+    output: &AudioOutput,
+    onend: impl FnOnce(PlaybackOutcome) + Send + 'static,
+) -> Result<PlaybackHandle> {
+    play_morse_with_progress(morse_code, options, tone, timing, output, |_| {}, onend)
+}
+
+/// Plays a given Morse code string like [`play_morse`], also calling
+/// `onprogress` from the playback thread as each element starts sounding.
+///
+/// Progress follows the position of the sink, so it pauses along with
+/// playback.
+///
+/// # Errors
+///
+/// Returns [`Error::Audio`] if the audio output cannot be opened.
+pub fn play_morse_with_progress(
+    morse_code: &str,
+    options: Options,
+    tone: Tone,
+    timing: Timing,
+    output: &AudioOutput,
+    mut onprogress: impl FnMut(Progress) + Send + 'static,
+    onend: impl FnOnce(PlaybackOutcome) + Send + 'static,
+) -> Result<PlaybackHandle> {
+    let elements = timeline(morse_code, &options, &timing);
+    let mut events = schedule(morse_code, &options, &timing)
+        .into_iter()
+        .peekable();
+
+    play_source(
+        output,
+        move |sample_rate| SynthSource(Synth::new(elements, tone, sample_rate)),
+        move |position| {
+            while let Some(progress) = events.next_if(|progress| progress.start <= position) {
+                onprogress(progress);
             }
-            rodio::buffer::SamplesBuffer::new(1, sample_rate, buffer)
-        };
+        },
+        onend,
+    )
+}
 
-        for char in morse_code.chars() {
-            match char {
-                c if c == options.dot => {
-                    let sine_wave = create_sine_wave(frequency, time_unit);
-                    stream_handle.play_raw(sine_wave.convert_samples()).unwrap();
-                    sleep(Duration::from_millis(u64::from(time_unit)));
-                }
-                c if c == options.dash => {
-                    let sine_wave = create_sine_wave(frequency, time_unit * 3);
-                    stream_handle.play_raw(sine_wave.convert_samples()).unwrap();
-                    sleep(Duration::from_millis(u64::from(time_unit) * 3));
+/// Plays mono samples in `-1.0..=1.0` at `sample_rate`, such as a pile-up
+/// rendered by [`mix`](crate::render::mix).
+///
+/// Playback happens on a background thread like [`play_morse`], and `onend`
+/// is called from that thread once the last sample has played or playback
+/// was stopped.
+///
+/// # Errors
+///
+/// Returns [`Error::Audio`] if the audio output cannot be opened.
+pub fn play_samples(
+    samples: Vec<f32>,
+    sample_rate: u32,
+    output: &AudioOutput,
+    onend: impl FnOnce(PlaybackOutcome) + Send + 'static,
+) -> Result<PlaybackHandle> {
+    play_source(
+        output,
+        move |_| SamplesBuffer::new(1, sample_rate.max(1), samples),
+        |_| {},
+        onend,
+    )
+}
+
+/// Plays the source built by `source`, which is given the sample rate of the
+/// opened stream, on a background thread. `onposition` is called with the
+/// position of the sink each time the thread checks it.
+fn play_source<S: Source + Send + 'static>(
+    output: &AudioOutput,
+    source: impl FnOnce(SampleRate) -> S + Send + 'static,
+    mut onposition: impl FnMut(Duration) + Send + 'static,
+    onend: impl FnOnce(PlaybackOutcome) + Send + 'static,
+) -> Result<PlaybackHandle> {
+    let output = output.clone();
+    let control = Arc::new(Control::new());
+    let (opened_tx, opened_rx) = mpsc::sync_channel(1);
+
+    let thread = spawn({
+        let control = Arc::clone(&control);
+        move || {
+            // rodio streams cannot move between threads on every platform,
+            // so the stream is opened by the thread that plays it.
+            let stream = match output.open() {
+                Ok(stream) => {
+                    let _ = opened_tx.send(Ok(()));
+                    stream
                 }
-                c if c == options.separator || c == options.space => {
-                    sleep(Duration::from_millis(u64::from(time_unit) * 3));
+                Err(err) => {
+                    let _ = opened_tx.send(Err(err));
+                    return;
                 }
-                _ => {
-                    sleep(Duration::from_millis(u64::from(time_unit) * 7));
+            };
+            let sink = Sink::connect_new(stream.mixer());
+            sink.append(source(stream.config().sample_rate()));
+
+            let mut outcome = PlaybackOutcome::Completed;
+            while !sink.empty() {
+                match control.wait(POLL_INTERVAL) {
+                    State::Playing => sink.play(),
+                    State::Paused => sink.pause(),
+                    State::Stopped => {
+                        sink.stop();
+                        outcome = PlaybackOutcome::Cancelled;
+                        break;
+                    }
                 }
+
+                onposition(sink.get_pos());
             }
 
-            sleep(Duration::from_millis(u64::from(time_unit)));
+            drop(onposition);
+            onend(outcome);
         }
     });
 
-    onend();
+    let thread = wait_for_stream(&opened_rx, thread)?;
+    Ok(PlaybackHandle { control, thread })
 }
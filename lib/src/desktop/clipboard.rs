@@ -0,0 +1,198 @@
+use std::{
+    env,
+    io::{self, Write},
+    process::{Command, Output, Stdio},
+};
+
+use crate::{Error, Result};
+
+/// Which X11 or Wayland selection to use.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Selection {
+    /// The clipboard, set by explicit copy commands.
+    #[default]
+    Clipboard,
+    /// The primary selection, set by selecting text and pasted with the
+    /// middle mouse button.
+    Primary,
+}
+
+/// The command-line clipboard tools, tried in order for the current session.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Tool {
+    /// `wl-copy` and `wl-paste` from wl-clipboard, for Wayland sessions.
+    WlClipboard,
+    Xclip,
+    Xsel,
+}
+
+impl Tool {
+    /// The tools that can reach the current session's clipboard. X11 tools
+    /// are kept as a fallback on Wayland, where they work through XWayland.
+    fn available() -> Vec<Self> {
+        let wayland = env::var_os("WAYLAND_DISPLAY").is_some();
+        let x11 = env::var_os("DISPLAY").is_some();
+
+        let mut tools = Vec::new();
+        if wayland {
+            tools.push(Self::WlClipboard);
+        }
+        if x11 {
+            tools.extend([Self::Xclip, Self::Xsel]);
+        }
+        tools
+    }
+
+    fn copy_command(self, selection: Selection) -> Command {
+        let primary = selection == Selection::Primary;
+        match self {
+            Self::WlClipboard => {
+                let mut command = Command::new("wl-copy");
+                if primary {
+                    command.arg("--primary");
+                }
+                command
+            }
+            Self::Xclip => {
+                let mut command = Command::new("xclip");
+                command.args(["-selection", if primary { "primary" } else { "clipboard" }]);
+                command
+            }
+            Self::Xsel => {
+                let mut command = Command::new("xsel");
+                command.args([if primary { "--primary" } else { "--clipboard" }, "--input"]);
+                command
+            }
+        }
+    }
+
+    fn paste_command(self, selection: Selection) -> Command {
+        let primary = selection == Selection::Primary;
+        match self {
+            Self::WlClipboard => {
+                let mut command = Command::new("wl-paste");
+                command.arg("--no-newline");
+                if primary {
+                    command.arg("--primary");
+                }
+                command
+            }
+            Self::Xclip => {
+                let mut command = Command::new("xclip");
+                command.args([
+                    "-selection",
+                    if primary { "primary" } else { "clipboard" },
+                    "-out",
+                ]);
+                command
+            }
+            Self::Xsel => {
+                let mut command = Command::new("xsel");
+                command.args([
+                    if primary { "--primary" } else { "--clipboard" },
+                    "--output",
+                ]);
+                command
+            }
+        }
+    }
+}
+
+/// Runs the first installed tool, returning `None` if none of them is.
+fn run_first(run: impl Fn(Tool) -> io::Result<Output>) -> Option<io::Result<Output>> {
+    Tool::available()
+        .into_iter()
+        .map(run)
+        .find(|output| !matches!(output, Err(err) if err.kind() == io::ErrorKind::NotFound))
+}
+
+fn clipboard_error(output: &Output) -> Error {
+    let message = String::from_utf8_lossy(&output.stderr);
+    Error::Clipboard(if message.trim().is_empty() {
+        output.status.to_string()
+    } else {
+        message.trim().to_string()
+    })
+}
+
+fn missing_tools() -> Error {
+    Error::Clipboard(
+        "no clipboard tool found; install wl-clipboard on Wayland or xclip or xsel on X11"
+            .to_string(),
+    )
+}
+
+/// Copies the given text to the clipboard.
+///
+/// # Errors
+///
+/// Returns [`Error::Clipboard`] if no clipboard tool is installed or the
+/// copy failed.
+pub fn copy_to_clipboard(text: &str) -> Result<()> {
+    copy_to_selection(text, Selection::Clipboard)
+}
+
+/// Copies the given text to the clipboard or the primary selection, through
+/// wl-clipboard on Wayland and xclip or xsel on X11.
+///
+/// # Errors
+///
+/// Returns [`Error::Clipboard`] if no clipboard tool is installed or the
+/// copy failed.
+pub fn copy_to_selection(text: &str, selection: Selection) -> Result<()> {
+    let output = run_first(|tool| {
+        // The tools fork to keep serving the selection, and the fork would
+        // hold piped output open, so only stdin is piped.
+        let mut child = tool
+            .copy_command(selection)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()?;
+        let written = child
+            .stdin
+            .take()
+            .map_or(Ok(()), |mut stdin| stdin.write_all(text.as_bytes()));
+        // Reap the tool even if it stopped reading, so it is not left behind
+        // as a zombie.
+        let output = child.wait_with_output()?;
+        written.map(|()| output)
+    })
+    .ok_or_else(missing_tools)?
+    .map_err(|err| Error::Clipboard(err.to_string()))?;
+
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(clipboard_error(&output))
+    }
+}
+
+/// Reads text from the clipboard.
+///
+/// # Errors
+///
+/// Returns [`Error::Clipboard`] if no clipboard tool is installed or the
+/// clipboard holds no text.
+pub fn read_from_clipboard() -> Result<String> {
+    read_from_selection(Selection::Clipboard)
+}
+
+/// Reads text from the clipboard or the primary selection, through
+/// wl-clipboard on Wayland and xclip or xsel on X11.
+///
+/// # Errors
+///
+/// Returns [`Error::Clipboard`] if no clipboard tool is installed or the
+/// selection holds no text.
+pub fn read_from_selection(selection: Selection) -> Result<String> {
+    let output = run_first(|tool| tool.paste_command(selection).stdin(Stdio::null()).output())
+        .ok_or_else(missing_tools)?
+        .map_err(|err| Error::Clipboard(err.to_string()))?;
+
+    if output.status.success() {
+        String::from_utf8(output.stdout).map_err(|err| Error::Clipboard(err.to_string()))
+    } else {
+        Err(clipboard_error(&output))
+    }
+}
@@ -0,0 +1,273 @@
+use std::{
+    sync::{
+        Arc,
+        mpsc::{self, RecvTimeoutError, Sender},
+    },
+    thread::{JoinHandle, spawn},
+    time::{Duration, Instant},
+};
+
+use cpal::{
+    BuildStreamError, Device, FromSample, SampleFormat, SizedSample, Stream, StreamConfig,
+    StreamError,
+    traits::{DeviceTrait, HostTrait, StreamTrait},
+};
+
+use super::{Control, POLL_INTERVAL, State, wait_for_stream};
+use crate::{
+    Error, Result,
+    decoder::{Decoder, Event, detect_frequency},
+};
+
+/// How much audio is collected to find the tone when no frequency is given.
+const DETECTION_WINDOW: Duration = Duration::from_secs(1);
+
+/// How often the WPM and signal strength are reported while no characters
+/// arrive.
+const UPDATE_INTERVAL: Duration = Duration::from_millis(100);
+
+/// What the decoder heard since the previous update.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ListenUpdate {
+    /// Characters and word gaps decoded since the previous update, if any.
+    pub events: Vec<Event>,
+    /// Frequency of the tone being decoded, in hertz.
+    pub frequency: f32,
+    /// Estimated sending speed in words per minute.
+    pub wpm: f32,
+    /// Signal-to-noise ratio of the tone in decibels.
+    pub signal_strength: f32,
+}
+
+/// A handle to a decoder listening on a background thread.
+///
+/// Dropping the handle does not stop listening; call [`ListenHandle::stop`]
+/// to close the input device.
+#[derive(Debug)]
+pub struct ListenHandle {
+    control: Arc<Control>,
+    thread: JoinHandle<Result<()>>,
+}
+
+impl ListenHandle {
+    /// Stops listening and closes the input device. Anything still being
+    /// decoded is reported in a last update.
+    pub fn stop(&self) {
+        self.control.set(State::Stopped);
+    }
+
+    /// Pauses the input stream. The decoder keeps its speed and level
+    /// estimates for when listening resumes.
+    pub fn pause(&self) {
+        self.control.set(State::Paused);
+    }
+
+    /// Resumes listening after [`ListenHandle::pause`].
+    pub fn resume(&self) {
+        self.control.set(State::Playing);
+    }
+
+    /// Returns `true` once the listening thread has exited, either because it
+    /// was stopped or because the input stream failed.
+    #[must_use]
+    pub fn is_finished(&self) -> bool {
+        self.thread.is_finished()
+    }
+
+    /// Blocks the current thread until listening has ended.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Audio`] if the input stream failed rather than being
+    /// stopped through [`ListenHandle::stop`].
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the listening thread panicked.
+    pub fn join(self) -> Result<()> {
+        self.thread.join().unwrap()
+    }
+}
+
+enum Message {
+    Samples(Vec<f32>),
+    Error(StreamError),
+}
+
+/// Decodes Morse code from the default audio input device.
+///
+/// Decoding happens on a background thread, which calls `onupdate` whenever
+/// characters are decoded and at least every 100 ms with the current speed
+/// and signal strength. If `frequency` is `None`, the tone is found from the
+/// first second of audio and updates start after it.
+///
+/// # Errors
+///
+/// Returns [`Error::Audio`] if the input device cannot be opened.
+pub fn listen(
+    frequency: Option<f32>,
+    onupdate: impl FnMut(ListenUpdate) + Send + 'static,
+) -> Result<ListenHandle> {
+    let control = Arc::new(Control::new());
+    let (opened_tx, opened_rx) = mpsc::sync_channel(1);
+
+    let thread = spawn({
+        let control = Arc::clone(&control);
+        move || {
+            // cpal streams cannot move between threads on every platform, so
+            // the stream is opened and dropped by the thread that reads it.
+            let (stream, messages, sample_rate) = match open() {
+                Ok(opened) => {
+                    let _ = opened_tx.send(Ok(()));
+                    opened
+                }
+                Err(err) => {
+                    let _ = opened_tx.send(Err(err));
+                    return Ok(());
+                }
+            };
+            run(
+                &stream,
+                &messages,
+                sample_rate,
+                frequency,
+                &control,
+                onupdate,
+            )
+        }
+    });
+
+    let thread = wait_for_stream(&opened_rx, thread)?;
+    Ok(ListenHandle { control, thread })
+}
+
+fn open() -> Result<(Stream, mpsc::Receiver<Message>, u32)> {
+    let device = cpal::default_host()
+        .default_input_device()
+        .ok_or_else(|| Error::Audio("no audio input device is available".to_string()))?;
+    let config = device.default_input_config()?;
+    let (messages_tx, messages_rx) = mpsc::channel();
+
+    let stream = match config.sample_format() {
+        SampleFormat::F32 => build::<f32>(&device, &config.config(), messages_tx),
+        SampleFormat::I16 => build::<i16>(&device, &config.config(), messages_tx),
+        SampleFormat::U16 => build::<u16>(&device, &config.config(), messages_tx),
+        SampleFormat::I32 => build::<i32>(&device, &config.config(), messages_tx),
+        format => {
+            return Err(Error::Audio(format!(
+                "unsupported input sample format: {format}"
+            )));
+        }
+    }?;
+    stream.play()?;
+
+    Ok((stream, messages_rx, config.sample_rate().0))
+}
+
+/// Opens an input stream that mixes each frame down to mono and sends it to
+/// the listening thread.
+fn build<T>(
+    device: &Device,
+    config: &StreamConfig,
+    messages: Sender<Message>,
+) -> Result<Stream, BuildStreamError>
+where
+    T: SizedSample,
+    f32: FromSample<T>,
+{
+    let channels = usize::from(config.channels).max(1);
+    let errors = messages.clone();
+
+    device.build_input_stream(
+        config,
+        move |data: &[T], _| {
+            let samples = data
+                .chunks(channels)
+                .map(|frame| {
+                    frame
+                        .iter()
+                        .map(|sample| sample.to_sample::<f32>())
+                        .sum::<f32>()
+                        / frame.len() as f32
+                })
+                .collect();
+            let _ = messages.send(Message::Samples(samples));
+        },
+        move |err| {
+            let _ = errors.send(Message::Error(err));
+        },
+        None,
+    )
+}
+
+fn run(
+    stream: &Stream,
+    messages: &mpsc::Receiver<Message>,
+    sample_rate: u32,
+    frequency: Option<f32>,
+    control: &Control,
+    mut onupdate: impl FnMut(ListenUpdate),
+) -> Result<()> {
+    let mut decoder = frequency.map(|frequency| Decoder::new(sample_rate, frequency));
+    let mut detection = Vec::new();
+    let detection_len = (DETECTION_WINDOW.as_secs_f32() * sample_rate as f32) as usize;
+    let mut events = Vec::new();
+    let mut last_update = Instant::now();
+    let mut paused = false;
+
+    loop {
+        match control.get() {
+            State::Stopped => break,
+            State::Paused if !paused => {
+                stream.pause()?;
+                paused = true;
+            }
+            State::Playing if paused => {
+                stream.play()?;
+                paused = false;
+            }
+            State::Playing | State::Paused => {}
+        }
+
+        match messages.recv_timeout(POLL_INTERVAL) {
+            Ok(Message::Samples(samples)) => match &mut decoder {
+                Some(decoder) => events.extend(decoder.push(&samples)),
+                None => {
+                    detection.extend(samples);
+                    if detection.len() >= detection_len {
+                        let mut detected =
+                            Decoder::new(sample_rate, detect_frequency(&detection, sample_rate));
+                        events.extend(detected.push(&std::mem::take(&mut detection)));
+                        decoder = Some(detected);
+                    }
+                }
+            },
+            Ok(Message::Error(err)) => return Err(err.into()),
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+
+        if let Some(decoder) = &decoder
+            && (!events.is_empty() || last_update.elapsed() >= UPDATE_INTERVAL)
+        {
+            onupdate(update(decoder, std::mem::take(&mut events)));
+            last_update = Instant::now();
+        }
+    }
+
+    if let Some(decoder) = &mut decoder {
+        events.extend(decoder.finish());
+        onupdate(update(decoder, events));
+    }
+
+    Ok(())
+}
+
+fn update(decoder: &Decoder, events: Vec<Event>) -> ListenUpdate {
+    ListenUpdate {
+        events,
+        frequency: decoder.frequency(),
+        wpm: decoder.wpm(),
+        signal_strength: decoder.signal_strength(),
+    }
+}
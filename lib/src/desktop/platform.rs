@@ -0,0 +1,165 @@
+use std::{
+    future::{Future, ready},
+    sync::{Arc, Mutex, MutexGuard},
+};
+
+use morsify::Options;
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+use self::{unsupported as clipboard, unsupported as speech};
+use super::{
+    AudioOutput, PlaybackHandle, play_morse_with_progress, play_samples, read_setting,
+    write_setting,
+};
+#[cfg(target_os = "linux")]
+use super::{clipboard, speech};
+use crate::{
+    Error, Result,
+    backend::Backend,
+    playback::{Completion, ProgressEvents},
+    timing::Timing,
+    tone::Tone,
+};
+#[cfg(target_os = "macos")]
+use crate::{macos as clipboard, macos as speech};
+
+/// The [`Backend`] for desktop systems.
+///
+/// Morse code plays through [`play_morse`](super::play_morse). Speech and the clipboard use the
+/// Linux tools or the macOS frameworks, and report [`Error::Speech`] or
+/// [`Error::Clipboard`] on other systems. Settings are files in the user's
+/// configuration directory. Desktops cannot vibrate, so
+/// [`Backend::vibrate_morse`] always reports [`Error::Haptic`]. Clones share
+/// the playback they can stop.
+#[derive(Debug, Clone, Default)]
+pub struct Platform {
+    output: AudioOutput,
+    playback: Arc<Mutex<Option<PlaybackHandle>>>,
+}
+
+impl Platform {
+    /// A backend that plays Morse code on the given audio output.
+    #[must_use]
+    pub fn with_output(output: AudioOutput) -> Self {
+        Self {
+            output,
+            ..Self::default()
+        }
+    }
+
+    /// Stops the current playback before anything new opens the output, so
+    /// the two never overlap. The lock is held until the next playback takes
+    /// its place.
+    fn stop_playback(&self) -> MutexGuard<'_, Option<PlaybackHandle>> {
+        let mut playback = self.playback.lock().unwrap();
+        if let Some(previous) = playback.take() {
+            previous.stop();
+        }
+        playback
+    }
+}
+
+impl Backend for Platform {
+    fn play_morse(
+        &self,
+        morse_code: &str,
+        options: Options,
+        tone: Tone,
+        timing: Timing,
+    ) -> Result<Completion> {
+        self.play_morse_with_progress(morse_code, options, tone, timing)
+            .map(|(completion, _)| completion)
+    }
+
+    fn play_morse_with_progress(
+        &self,
+        morse_code: &str,
+        options: Options,
+        tone: Tone,
+        timing: Timing,
+    ) -> Result<(Completion, ProgressEvents)> {
+        let (completer, completion) = Completion::new();
+        let (sender, events) = ProgressEvents::new();
+        let mut playback = self.stop_playback();
+        let handle = play_morse_with_progress(
+            morse_code,
+            options,
+            tone,
+            timing,
+            &self.output,
+            move |progress| sender.send(progress),
+            |outcome| completer.complete(outcome),
+        )?;
+        *playback = Some(handle);
+        Ok((completion, events))
+    }
+
+    fn play_samples(&self, samples: Vec<f32>, sample_rate: u32) -> Result<Completion> {
+        let (completer, completion) = Completion::new();
+        let mut playback = self.stop_playback();
+        let handle = play_samples(samples, sample_rate, &self.output, |outcome| {
+            completer.complete(outcome);
+        })?;
+        *playback = Some(handle);
+        Ok(completion)
+    }
+
+    fn vibrate_morse(&self, _morse_code: &str, _options: &Options, _timing: &Timing) -> Result<()> {
+        Err(Error::Haptic(
+            "vibration is not supported on this system".to_string(),
+        ))
+    }
+
+    fn play_text(&self, text: &str) -> Result<Completion> {
+        let (completer, completion) = Completion::new();
+        speech::play_text(text, |outcome| completer.complete(outcome))?;
+        Ok(completion)
+    }
+
+    fn stop(&self) {
+        drop(self.stop_playback());
+        speech::stop_text();
+    }
+
+    fn copy_to_clipboard(&self, text: &str) -> impl Future<Output = Result<()>> {
+        ready(clipboard::copy_to_clipboard(text))
+    }
+
+    fn read_clipboard(&self) -> impl Future<Output = Result<String>> {
+        ready(clipboard::read_from_clipboard())
+    }
+
+    fn read_setting(&self, key: &str) -> Result<Option<String>> {
+        read_setting(key)
+    }
+
+    fn write_setting(&self, key: &str, value: &str) -> Result<()> {
+        write_setting(key, value)
+    }
+}
+
+/// Stand-ins for the speech and clipboard functions on systems without them.
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+mod unsupported {
+    use crate::{Error, Result, playback::PlaybackOutcome};
+
+    pub fn play_text(_text: &str, _onend: impl FnOnce(PlaybackOutcome)) -> Result<()> {
+        Err(Error::Speech(
+            "speech is not supported on this system".to_string(),
+        ))
+    }
+
+    pub fn stop_text() {}
+
+    pub fn copy_to_clipboard(_text: &str) -> Result<()> {
+        Err(Error::Clipboard(
+            "the clipboard is not supported on this system".to_string(),
+        ))
+    }
+
+    pub fn read_from_clipboard() -> Result<String> {
+        Err(Error::Clipboard(
+            "the clipboard is not supported on this system".to_string(),
+        ))
+    }
+}
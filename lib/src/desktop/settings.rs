@@ -0,0 +1,76 @@
+use std::{
+    env, fs, io,
+    path::{Path, PathBuf},
+};
+
+use crate::{Error, Result};
+
+/// The directory settings are saved in, under the user's configuration
+/// directory.
+const APP_DIR: &str = "dot-dash";
+
+/// The user's configuration directory: `%APPDATA%` on Windows,
+/// `~/Library/Application Support` on macOS and `$XDG_CONFIG_HOME` or
+/// `~/.config` elsewhere.
+fn config_dir() -> Result<PathBuf> {
+    let var = |name| {
+        env::var_os(name)
+            .filter(|value| !value.is_empty())
+            .map(PathBuf::from)
+    };
+    let dir = if cfg!(target_os = "windows") {
+        var("APPDATA")
+    } else if cfg!(target_os = "macos") {
+        var("HOME").map(|home| home.join("Library/Application Support"))
+    } else {
+        var("XDG_CONFIG_HOME").or_else(|| var("HOME").map(|home| home.join(".config")))
+    };
+    dir.ok_or_else(|| Error::Storage("no configuration directory is set".to_string()))
+}
+
+/// The file a setting is saved in.
+fn setting_path(key: &str) -> Result<PathBuf> {
+    let valid = !key.is_empty()
+        && key
+            .chars()
+            .all(|char| char.is_ascii_alphanumeric() || matches!(char, '-' | '_'));
+    if !valid {
+        return Err(Error::Storage(format!(
+            "{key:?} is not a valid setting name"
+        )));
+    }
+    Ok(config_dir()?.join(APP_DIR).join(key))
+}
+
+fn storage_error(path: &Path, err: &io::Error) -> Error {
+    Error::Storage(format!("{}: {err}", path.display()))
+}
+
+/// Reads the setting saved under `key`, or `None` if nothing was saved.
+///
+/// # Errors
+///
+/// Returns [`Error::Storage`] if `key` is not a valid name or the setting
+/// file cannot be read.
+pub fn read_setting(key: &str) -> Result<Option<String>> {
+    let path = setting_path(key)?;
+    match fs::read_to_string(&path) {
+        Ok(value) => Ok(Some(value)),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(storage_error(&path, &err)),
+    }
+}
+
+/// Saves `value` under `key`, creating the settings directory if needed.
+///
+/// # Errors
+///
+/// Returns [`Error::Storage`] if `key` is not a valid name or the setting
+/// file cannot be written.
+pub fn write_setting(key: &str, value: &str) -> Result<()> {
+    let path = setting_path(key)?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|err| storage_error(dir, &err))?;
+    }
+    fs::write(&path, value).map_err(|err| storage_error(&path, &err))
+}
@@ -0,0 +1,312 @@
+use std::{
+    io::{self, Write},
+    process::{Child, Command, ExitStatus, Stdio},
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, Ordering},
+    },
+    thread::{sleep, spawn},
+};
+
+use super::POLL_INTERVAL;
+use crate::{Error, Result, playback::PlaybackOutcome};
+
+/// Speaking rate of espeak-ng at a [`Voice::rate`] of `1.0`, in words per
+/// minute.
+const ESPEAK_WPM: f32 = 175.0;
+
+/// Voice settings for [`play_text_with_voice`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Voice {
+    /// Name of the synthesis voice, as returned by [`voices`]. `None` uses
+    /// the synthesizer's default voice.
+    pub name: Option<String>,
+    /// Speaking rate relative to normal speech: `2.0` is twice as fast and
+    /// `0.5` half as fast.
+    pub rate: f32,
+}
+
+impl Default for Voice {
+    fn default() -> Self {
+        Self {
+            name: None,
+            rate: 1.0,
+        }
+    }
+}
+
+/// The speech programs text can be read with, in order of preference.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Synthesizer {
+    /// `spd-say`, the client of the speech-dispatcher service.
+    SpeechDispatcher,
+    /// The espeak-ng synthesizer, run directly.
+    Espeak,
+}
+
+impl Synthesizer {
+    const ALL: [Self; 2] = [Self::SpeechDispatcher, Self::Espeak];
+
+    const fn program(self) -> &'static str {
+        match self {
+            Self::SpeechDispatcher => "spd-say",
+            Self::Espeak => "espeak-ng",
+        }
+    }
+
+    fn speak(self, text: &str, voice: &Voice) -> io::Result<Child> {
+        let mut command = Command::new(self.program());
+        command.stdout(Stdio::null()).stderr(Stdio::null());
+
+        match self {
+            Self::SpeechDispatcher => {
+                let rate = ((voice.rate - 1.0) * 100.0).clamp(-100.0, 100.0);
+                command.arg("--wait").arg(format!("--rate={rate:.0}"));
+                if let Some(name) = &voice.name {
+                    command.arg(format!("--synthesis-voice={name}"));
+                }
+                command.arg("--").arg(text).stdin(Stdio::null());
+            }
+            Self::Espeak => {
+                let wpm = (ESPEAK_WPM * voice.rate).clamp(80.0, 500.0);
+                command.arg(format!("-s{wpm:.0}"));
+                if let Some(name) = &voice.name {
+                    command.arg(format!("-v{name}"));
+                }
+                // Text on the command line could be mistaken for options.
+                command.arg("--stdin").stdin(Stdio::piped());
+            }
+        }
+
+        command.spawn()
+    }
+
+    /// Silences `child`, the synthesizer speaking an utterance.
+    ///
+    /// Killing `spd-say` would only stop the client: speech-dispatcher keeps
+    /// speaking the text it was sent, so the service is told to cancel it
+    /// instead, and the waiting client then exits by itself.
+    fn cancel(self, child: &Mutex<Child>) {
+        match self {
+            Self::SpeechDispatcher => {
+                let _ = Command::new(self.program())
+                    .arg("--cancel")
+                    .stdout(Stdio::null())
+                    .stderr(Stdio::null())
+                    .status();
+            }
+            Self::Espeak => {
+                let _ = child.lock().unwrap().kill();
+            }
+        }
+    }
+
+    /// How an utterance ended, from the synthesizer's exit `status` and
+    /// whether it was `cancelled`. A cancelled synthesizer may exit with any
+    /// status, including the signal it was killed with.
+    fn outcome(self, status: ExitStatus, cancelled: bool) -> PlaybackOutcome {
+        if cancelled {
+            PlaybackOutcome::Cancelled
+        } else if status.success() {
+            PlaybackOutcome::Completed
+        } else {
+            PlaybackOutcome::Failed(Error::Speech(format!(
+                "{} exited with {status}",
+                self.program()
+            )))
+        }
+    }
+
+    /// Lists the voices the synthesizer offers, or `None` if it is not
+    /// installed.
+    fn voices(self) -> Option<Vec<String>> {
+        let (flag, column) = match self {
+            Self::SpeechDispatcher => ("--list-synthesis-voices", 0),
+            Self::Espeak => ("--voices", 1),
+        };
+        let output = Command::new(self.program()).arg(flag).output().ok()?;
+        if !output.status.success() {
+            return None;
+        }
+
+        let voices = String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .skip(1)
+            .filter_map(|line| line.split_whitespace().nth(column))
+            .map(str::to_string)
+            .collect();
+        Some(voices)
+    }
+}
+
+/// The utterance currently being spoken.
+#[derive(Debug)]
+struct Speaking {
+    synthesizer: Synthesizer,
+    child: Arc<Mutex<Child>>,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl Speaking {
+    /// Tells the speaking thread the utterance was cancelled, and silences
+    /// it right away so a following utterance does not overlap it.
+    fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+        self.synthesizer.cancel(&self.child);
+    }
+}
+
+static SPEAKING: Mutex<Option<Speaking>> = Mutex::new(None);
+
+/// Reads the given text aloud with the default voice.
+///
+/// See [`play_text_with_voice`].
+///
+/// # Errors
+///
+/// Returns [`Error::Speech`] if no speech synthesizer is installed.
+pub fn play_text(text: &str, onend: impl FnOnce(PlaybackOutcome) + Send + 'static) -> Result<()> {
+    play_text_with_voice(text, &Voice::default(), onend)
+}
+
+/// Reads the given text aloud through speech-dispatcher, or espeak-ng if
+/// speech-dispatcher is not installed.
+///
+/// Starting a new utterance cancels the previous one. `onend` is called from
+/// a background thread once the synthesizer has finished speaking, was
+/// stopped, or failed.
+///
+/// # Errors
+///
+/// Returns [`Error::Speech`] if neither synthesizer is installed. `onend` is
+/// not called in that case.
+pub fn play_text_with_voice(
+    text: &str,
+    voice: &Voice,
+    onend: impl FnOnce(PlaybackOutcome) + Send + 'static,
+) -> Result<()> {
+    let mut speaking = SPEAKING.lock().unwrap();
+    if let Some(previous) = speaking.take() {
+        previous.cancel();
+    }
+
+    let (synthesizer, mut child) = spawn_synthesizer(text, voice)?;
+    let stdin = child.stdin.take();
+    let child = Arc::new(Mutex::new(child));
+    let cancelled = Arc::new(AtomicBool::new(false));
+    *speaking = Some(Speaking {
+        synthesizer,
+        child: Arc::clone(&child),
+        cancelled: Arc::clone(&cancelled),
+    });
+    drop(speaking);
+
+    let text = text.to_string();
+    spawn(move || {
+        if let Some(mut stdin) = stdin {
+            let _ = stdin.write_all(text.as_bytes());
+        }
+
+        let outcome = loop {
+            let status = child.lock().unwrap().try_wait();
+            match status {
+                // The flag is read after the exit, since cancelling is
+                // what may have ended the synthesizer.
+                Ok(Some(status)) => {
+                    break synthesizer.outcome(status, cancelled.load(Ordering::Relaxed));
+                }
+                Ok(None) => sleep(POLL_INTERVAL),
+                Err(err) => break PlaybackOutcome::Failed(Error::Speech(err.to_string())),
+            }
+        };
+
+        let mut speaking = SPEAKING.lock().unwrap();
+        if speaking
+            .as_ref()
+            .is_some_and(|current| Arc::ptr_eq(&current.cancelled, &cancelled))
+        {
+            *speaking = None;
+        }
+        drop(speaking);
+
+        onend(outcome);
+    });
+
+    Ok(())
+}
+
+fn spawn_synthesizer(text: &str, voice: &Voice) -> Result<(Synthesizer, Child)> {
+    for synthesizer in Synthesizer::ALL {
+        match synthesizer.speak(text, voice) {
+            Ok(child) => return Ok((synthesizer, child)),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+            Err(err) => {
+                return Err(Error::Speech(format!(
+                    "cannot run {}: {err}",
+                    synthesizer.program()
+                )));
+            }
+        }
+    }
+
+    Err(Error::Speech(
+        "neither spd-say nor espeak-ng is installed".to_string(),
+    ))
+}
+
+/// Stops the utterance started by [`play_text`], if one is still being
+/// spoken. Its `onend` is called with [`PlaybackOutcome::Cancelled`].
+pub fn stop_text() {
+    if let Some(speaking) = SPEAKING.lock().unwrap().take() {
+        speaking.cancel();
+    }
+}
+
+/// Lists the voices [`Voice::name`] can select, from the synthesizer
+/// [`play_text`] would use.
+///
+/// # Errors
+///
+/// Returns [`Error::Speech`] if neither synthesizer is installed.
+pub fn voices() -> Result<Vec<String>> {
+    Synthesizer::ALL
+        .into_iter()
+        .find_map(Synthesizer::voices)
+        .ok_or_else(|| Error::Speech("neither spd-say nor espeak-ng is installed".to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::os::unix::process::ExitStatusExt;
+
+    use super::*;
+
+    #[test]
+    fn outcome_follows_the_exit_status() {
+        for synthesizer in Synthesizer::ALL {
+            let exited = |code| ExitStatus::from_raw(code << 8);
+            assert!(matches!(
+                synthesizer.outcome(exited(0), false),
+                PlaybackOutcome::Completed
+            ));
+            assert!(matches!(
+                synthesizer.outcome(exited(1), false),
+                PlaybackOutcome::Failed(Error::Speech(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn cancelled_speech_is_not_a_failure() {
+        // SIGKILL, as a killed espeak-ng reports.
+        let killed = ExitStatus::from_raw(9);
+        assert!(matches!(
+            Synthesizer::Espeak.outcome(killed, true),
+            PlaybackOutcome::Cancelled
+        ));
+        assert!(matches!(
+            Synthesizer::SpeechDispatcher.outcome(ExitStatus::from_raw(0), true),
+            PlaybackOutcome::Cancelled
+        ));
+    }
+}
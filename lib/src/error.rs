@@ -0,0 +1,74 @@
+use std::fmt;
+
+/// Errors reported by the playback, speech, vibration, clipboard, file,
+/// alphabet table and settings functions.
+///
+/// Errors from the platform APIs are kept as their message, so the same type
+/// can be shown in any of the UIs and carried by [`PlaybackOutcome`].
+///
+/// [`PlaybackOutcome`]: crate::playback::PlaybackOutcome
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Error {
+    /// No audio device is available, or the audio output or input failed.
+    Audio(String),
+    /// Speech synthesis is unavailable or could not read the text.
+    Speech(String),
+    /// The device cannot vibrate, or the vibration pattern was refused.
+    Haptic(String),
+    /// The clipboard could not be read or written, for example because the
+    /// permission was denied.
+    Clipboard(String),
+    /// A WAV file could not be read or written.
+    Wav(String),
+    /// An alphabet table could not be read, or its mappings conflict.
+    Table(String),
+    /// A saved setting could not be read, written or parsed.
+    Storage(String),
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Audio(message) => write!(f, "audio error: {message}"),
+            Self::Speech(message) => write!(f, "speech error: {message}"),
+            Self::Haptic(message) => write!(f, "haptic error: {message}"),
+            Self::Clipboard(message) => write!(f, "clipboard error: {message}"),
+            Self::Wav(message) => write!(f, "WAV error: {message}"),
+            Self::Table(message) => write!(f, "alphabet table error: {message}"),
+            Self::Storage(message) => write!(f, "storage error: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<hound::Error> for Error {
+    fn from(err: hound::Error) -> Self {
+        Self::Wav(err.to_string())
+    }
+}
+
+macro_rules! audio_errors {
+    ($($error:ty),* $(,)?) => {
+        $(
+            #[cfg(not(target_arch = "wasm32"))]
+            impl From<$error> for Error {
+                fn from(err: $error) -> Self {
+                    Self::Audio(err.to_string())
+                }
+            }
+        )*
+    };
+}
+
+audio_errors!(
+    cpal::BuildStreamError,
+    cpal::DefaultStreamConfigError,
+    cpal::DevicesError,
+    cpal::PauseStreamError,
+    cpal::PlayStreamError,
+    cpal::StreamError,
+    rodio::StreamError,
+);
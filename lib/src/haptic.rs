@@ -0,0 +1,29 @@
+use std::time::Duration;
+
+use morsify::Options;
+
+use crate::{timeline::timeline, timing::Timing};
+
+/// The on/off pattern of a Morse code string for a vibration motor, in
+/// milliseconds.
+///
+/// Durations alternate between vibrating and pausing, starting with a
+/// vibration, which is the pattern `navigator.vibrate` takes. Element
+/// boundaries are rounded to the millisecond from the start of the message,
+/// so rounding does not drift over long messages.
+#[must_use]
+pub fn vibration_pattern(morse_code: &str, options: &Options, timing: &Timing) -> Vec<u32> {
+    let mut end = Duration::ZERO;
+    let mut previous = 0;
+
+    timeline(morse_code, options, timing)
+        .into_iter()
+        .map(|element| {
+            end += element.duration;
+            let boundary = u32::try_from((end.as_micros() + 500) / 1000).unwrap_or(u32::MAX);
+            let duration = boundary - previous;
+            previous = boundary;
+            duration
+        })
+        .collect()
+}
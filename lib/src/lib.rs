@@ -1,6 +1,24 @@
+pub mod alphabet;
+pub mod backend;
+pub mod contest;
+pub mod decoder;
 #[cfg(not(target_arch = "wasm32"))]
 pub mod desktop;
+mod error;
+pub mod haptic;
 #[cfg(target_os = "macos")]
 pub mod macos;
+pub mod playback;
+pub mod practice;
+pub mod progress;
+mod random;
+pub mod render;
+pub mod timeline;
+pub mod timing;
+pub mod tone;
+pub mod training;
+pub mod translate;
 #[cfg(target_arch = "wasm32")]
 pub mod web;
+
+pub use error::{Error, Result};
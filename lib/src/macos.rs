@@ -1,37 +1,162 @@
 #![allow(deprecated)]
 
-use objc2::runtime::ProtocolObject;
-use objc2_app_kit::{NSPasteboard, NSSpeechSynthesizer};
+use std::cell::RefCell;
+
+use objc2::{
+    DefinedClass, MainThreadMarker, MainThreadOnly, define_class, msg_send,
+    rc::Retained,
+    runtime::{NSObject, NSObjectProtocol, ProtocolObject},
+};
+use objc2_app_kit::{
+    NSPasteboard, NSPasteboardTypeString, NSSpeechSynthesizer, NSSpeechSynthesizerDelegate,
+};
 use objc2_foundation::{NSArray, NSString};
 
-pub fn play_text(text: &str, mut onend: impl FnMut() + 'static) {
+use crate::{Error, Result, playback::PlaybackOutcome};
+
+struct SpeechDelegateIvars {
+    synth: Retained<NSSpeechSynthesizer>,
+    onend: RefCell<Option<Box<dyn FnOnce(PlaybackOutcome)>>>,
+}
+
+define_class!(
+    // SAFETY:
+    // - The superclass NSObject does not have any subclassing requirements.
+    // - `SpeechDelegate` does not implement `Drop`.
+    #[unsafe(super(NSObject))]
+    #[thread_kind = MainThreadOnly]
+    #[ivars = SpeechDelegateIvars]
+    struct SpeechDelegate;
+
+    unsafe impl NSObjectProtocol for SpeechDelegate {}
+
+    unsafe impl NSSpeechSynthesizerDelegate for SpeechDelegate {
+        #[unsafe(method(speechSynthesizer:didFinishSpeaking:))]
+        fn did_finish_speaking(&self, _sender: &NSSpeechSynthesizer, finished_speaking: bool) {
+            let outcome = if finished_speaking {
+                PlaybackOutcome::Completed
+            } else {
+                PlaybackOutcome::Cancelled
+            };
+            self.finish(outcome);
+            SPEAKING.with_borrow_mut(|speaking| {
+                if speaking
+                    .as_deref()
+                    .is_some_and(|current| std::ptr::eq(current, self))
+                {
+                    *speaking = None;
+                }
+            });
+        }
+    }
+);
+
+impl SpeechDelegate {
+    fn new(
+        mtm: MainThreadMarker,
+        synth: Retained<NSSpeechSynthesizer>,
+        onend: impl FnOnce(PlaybackOutcome) + 'static,
+    ) -> Retained<Self> {
+        let this = Self::alloc(mtm).set_ivars(SpeechDelegateIvars {
+            synth,
+            onend: RefCell::new(Some(Box::new(onend))),
+        });
+        unsafe { msg_send![super(this), init] }
+    }
+
+    fn finish(&self, outcome: PlaybackOutcome) {
+        if let Some(onend) = self.ivars().onend.borrow_mut().take() {
+            onend(outcome);
+        }
+    }
+}
+
+thread_local! {
+    /// The delegate of the utterance currently being spoken. The synthesizer
+    /// only holds a weak reference to its delegate, and the delegate keeps the
+    /// synthesizer alive until it has finished speaking.
+    static SPEAKING: RefCell<Option<Retained<SpeechDelegate>>> = const { RefCell::new(None) };
+}
+
+/// Reads the given text aloud using `NSSpeechSynthesizer`.
+///
+/// `onend` is called once the synthesizer has finished speaking or was
+/// stopped. Speech must be started from the main thread, since the
+/// synthesizer reports completion on the main run loop.
+///
+/// # Errors
+///
+/// Returns [`Error::Speech`] if called off the main thread or if the
+/// synthesizer refuses the text. `onend` is not called in that case.
+pub fn play_text(text: &str, onend: impl FnOnce(PlaybackOutcome) + 'static) -> Result<()> {
+    let Some(mtm) = MainThreadMarker::new() else {
+        return Err(Error::Speech(
+            "speech must be started from the main thread".to_string(),
+        ));
+    };
+
+    if let Some(previous) = SPEAKING.with_borrow_mut(Option::take) {
+        unsafe { previous.ivars().synth.stopSpeaking() };
+        previous.finish(PlaybackOutcome::Cancelled);
+    }
+
     let synth = unsafe { NSSpeechSynthesizer::new() };
+    let delegate = SpeechDelegate::new(mtm, synth, onend);
+    let synth = &delegate.ivars().synth;
+    unsafe { synth.setDelegate(Some(ProtocolObject::from_ref(&*delegate))) };
+
     let text = NSString::from_str(text);
+    if !unsafe { synth.startSpeakingString(&text) } {
+        return Err(Error::Speech(
+            "the speech synthesizer refused the text".to_string(),
+        ));
+    }
 
-    unsafe { synth.startSpeakingString(&text) };
+    SPEAKING.with_borrow_mut(|speaking| *speaking = Some(delegate));
+    Ok(())
+}
 
-    onend();
+/// Stops the utterance started by [`play_text`], if one is still being
+/// spoken. Its `onend` is called with [`PlaybackOutcome::Cancelled`].
+pub fn stop_text() {
+    if let Some(speaking) = SPEAKING.with_borrow_mut(Option::take) {
+        unsafe { speaking.ivars().synth.stopSpeaking() };
+        speaking.finish(PlaybackOutcome::Cancelled);
+    }
 }
 
-/// Copies the given text to the clipboard.
+/// Copies the given text to the general pasteboard.
 ///
-/// # Panics
+/// # Errors
 ///
-/// This function may panic if:
-/// - The `NSString::from_str(text)` fails to create a new `NSString` object. This is unlikely but could happen if the underlying Objective-C API fails unexpectedly.
-/// - `NSPasteboard::generalPasteboard()` fails to retrieve the general pasteboard instance. This is also unlikely but can occur if the macOS API is not available.
-/// - `pasteboard.clearContents()` fails to clear the pasteboard contents. This might happen if the pasteboard API fails.
-/// - `ProtocolObject::from_retained(text)` fails to create a `ProtocolObject` from the `NSString`. This could occur if the Objective-C runtime has issues.
-/// - `NSArray::from_vec(vec![obj])` fails to create an `NSArray` containing the `ProtocolObject`. This might happen if there's an issue with the Objective-C array API.
-/// - `pasteboard.writeObjects(&objects)` returns `false`, indicating that writing to the pasteboard failed.
-pub fn copy_to_clipboard(text: &str) {
-    unsafe {
+/// Returns [`Error::Clipboard`] if the pasteboard refuses the text.
+pub fn copy_to_clipboard(text: &str) -> Result<()> {
+    let written = unsafe {
         let text = NSString::from_str(text);
         let pasteboard = NSPasteboard::generalPasteboard();
         let _ = pasteboard.clearContents();
         let obj = ProtocolObject::from_retained(text);
         let objects = NSArray::from_retained_slice(&[obj]);
-        let res = pasteboard.writeObjects(&objects);
-        assert!(res, "Failed writing to pasteboard");
+        pasteboard.writeObjects(&objects)
     };
+
+    if written {
+        Ok(())
+    } else {
+        Err(Error::Clipboard(
+            "failed writing to the pasteboard".to_string(),
+        ))
+    }
+}
+
+/// Reads text from the general pasteboard.
+///
+/// # Errors
+///
+/// Returns [`Error::Clipboard`] if the pasteboard holds no text.
+pub fn read_from_clipboard() -> Result<String> {
+    let text = unsafe { NSPasteboard::generalPasteboard().stringForType(NSPasteboardTypeString) };
+
+    text.map(|text| text.to_string())
+        .ok_or_else(|| Error::Clipboard("the pasteboard holds no text".to_string()))
 }
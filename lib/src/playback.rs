@@ -0,0 +1,144 @@
+use std::{
+    collections::VecDeque,
+    future::{Future, poll_fn},
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll, Waker},
+};
+
+use crate::{Error, progress::Progress};
+
+/// How a playback ended, passed to the `onend` callback of the platform
+/// backends.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PlaybackOutcome {
+    /// The last element was played.
+    Completed,
+    /// Playback was stopped before it reached the end.
+    Cancelled,
+    /// Playback was interrupted by an error after it had started.
+    Failed(Error),
+}
+
+#[derive(Debug, Default)]
+struct Shared {
+    outcome: Option<PlaybackOutcome>,
+    waker: Option<Waker>,
+}
+
+/// A future that resolves to the [`PlaybackOutcome`] once a playback started
+/// through a [`Backend`] has ended.
+///
+/// It can be awaited on any executor, whichever thread the backend reports
+/// the outcome from.
+///
+/// [`Backend`]: crate::backend::Backend
+#[derive(Debug)]
+pub struct Completion(Arc<Mutex<Shared>>);
+
+/// The sending half of a [`Completion`], moved into an `onend` callback.
+/// Dropping it without an outcome resolves the completion as
+/// [`PlaybackOutcome::Cancelled`].
+#[derive(Debug)]
+pub(crate) struct Completer(Arc<Mutex<Shared>>);
+
+impl Completion {
+    pub(crate) fn new() -> (Completer, Self) {
+        let shared = Arc::new(Mutex::new(Shared::default()));
+        (Completer(Arc::clone(&shared)), Self(shared))
+    }
+}
+
+impl Future for Completion {
+    type Output = PlaybackOutcome;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<PlaybackOutcome> {
+        let mut shared = self.0.lock().unwrap();
+        match shared.outcome.take() {
+            Some(outcome) => Poll::Ready(outcome),
+            None => {
+                shared.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+impl Completer {
+    pub(crate) fn complete(self, outcome: PlaybackOutcome) {
+        self.0.lock().unwrap().outcome = Some(outcome);
+    }
+}
+
+impl Drop for Completer {
+    fn drop(&mut self) {
+        let mut shared = self.0.lock().unwrap();
+        shared.outcome.get_or_insert(PlaybackOutcome::Cancelled);
+        if let Some(waker) = shared.waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Queue {
+    events: VecDeque<Progress>,
+    closed: bool,
+    waker: Option<Waker>,
+}
+
+/// The [`Progress`] events of a playback started through
+/// [`Backend::play_morse_with_progress`], received as they happen.
+///
+/// [`Backend::play_morse_with_progress`]: crate::backend::Backend::play_morse_with_progress
+#[derive(Debug)]
+pub struct ProgressEvents(Arc<Mutex<Queue>>);
+
+/// The sending half of [`ProgressEvents`], moved into an `onprogress`
+/// callback. Dropping it ends the events.
+#[derive(Debug)]
+pub(crate) struct ProgressSender(Arc<Mutex<Queue>>);
+
+impl ProgressEvents {
+    pub(crate) fn new() -> (ProgressSender, Self) {
+        let queue = Arc::new(Mutex::new(Queue::default()));
+        (ProgressSender(Arc::clone(&queue)), Self(queue))
+    }
+
+    /// Waits for the next event. Returns `None` once playback has ended and
+    /// every event has been received.
+    pub async fn next(&mut self) -> Option<Progress> {
+        poll_fn(|cx| {
+            let mut queue = self.0.lock().unwrap();
+            match queue.events.pop_front() {
+                Some(progress) => Poll::Ready(Some(progress)),
+                None if queue.closed => Poll::Ready(None),
+                None => {
+                    queue.waker = Some(cx.waker().clone());
+                    Poll::Pending
+                }
+            }
+        })
+        .await
+    }
+}
+
+impl ProgressSender {
+    pub(crate) fn send(&self, progress: Progress) {
+        let mut queue = self.0.lock().unwrap();
+        queue.events.push_back(progress);
+        if let Some(waker) = queue.waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+impl Drop for ProgressSender {
+    fn drop(&mut self) {
+        let mut queue = self.0.lock().unwrap();
+        queue.closed = true;
+        if let Some(waker) = queue.waker.take() {
+            waker.wake();
+        }
+    }
+}
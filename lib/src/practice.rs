@@ -0,0 +1,602 @@
+use std::{collections::BTreeMap, time::Duration};
+
+use morsify::Options;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    Error, Result,
+    contest::{Contest, Exchange},
+    progress::schedule,
+    random::Random,
+    timeline::ElementKind,
+    timing::Timing,
+    training::{GROUP_LEN, KOCH_ORDER},
+    translate::{Translation, encode, text_range},
+};
+
+/// The setting [`Stats`] are saved under with
+/// [`Backend::write_setting`](crate::backend::Backend::write_setting).
+pub const STATS_KEY: &str = "practice-stats";
+
+/// The number of callsigns the one in an exercise is picked from, favouring
+/// those with weak characters.
+const CALLSIGN_CHOICES: usize = 4;
+
+/// The longest response time that still adds to a character's weight, in
+/// seconds.
+const MAX_LATENCY_WEIGHT: f64 = 2.0;
+
+/// Common English words and the abbreviations heard on the air.
+const WORDS: &[&str] = &[
+    "THE", "AND", "FOR", "ARE", "BUT", "NOT", "YOU", "ALL", "ANY", "CAN", "HAD", "HER", "WAS",
+    "ONE", "OUR", "OUT", "DAY", "GET", "HAS", "HIM", "HIS", "HOW", "MAN", "NEW", "NOW", "OLD",
+    "SEE", "TWO", "WAY", "WHO", "BOY", "DID", "ITS", "LET", "PUT", "SAY", "SHE", "TOO", "USE",
+    "ABOUT", "THERE", "WHICH", "THEIR", "WOULD", "QUICK", "JUMPED", "ZERO", "RADIO", "MORSE",
+    "SIGNAL", "POWER", "WEATHER", "CQ", "DE", "RST", "QTH", "QSL", "QRZ", "QSO", "TNX", "FB", "OM",
+    "WX", "ANT", "RIG", "HR", "UR", "ES", "73", "5NN",
+];
+
+const NAMES: &[&str] = &[
+    "JOHN", "MARY", "BOB", "ANNA", "PETE", "SUE", "JIM", "EVA", "TOM", "LIZ", "KEN", "ROSA",
+    "HANS", "YUKI", "IVAN", "OLGA",
+];
+
+const QTHS: &[&str] = &[
+    "BOSTON", "LONDON", "BERLIN", "TOKYO", "PARIS", "OSLO", "DENVER", "SYDNEY", "MADRID", "DUBLIN",
+    "ROME", "AUSTIN",
+];
+
+const REPORTS: &[&str] = &["599", "589", "579", "559", "449"];
+
+const WEATHER: &[&str] = &["SUNNY", "CLOUDY", "RAIN", "SNOW", "WINDY", "FOG"];
+
+/// Exchanges of a contact, between the station `{to}` and the station
+/// `{from}` that sends them.
+const QSO: &[&str] = &[
+    "CQ CQ CQ DE {from} {from} {from} K",
+    "{to} DE {from} GM TNX FER CALL UR RST {rst} {rst} NAME {name} QTH {qth} HW? {to} DE {from} K",
+    "{to} DE {from} R TNX RPRT {rst} NAME {name} {name} QTH {qth} WX {wx} {to} DE {from} K",
+    "{to} DE {from} FB {name} TNX FER QSO 73 ES GL {to} DE {from} SK",
+];
+
+/// What a practice session sends.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Material {
+    /// Groups of five random letters, digits and punctuation.
+    #[default]
+    Groups,
+    /// Amateur radio callsigns such as `DL2XY`.
+    Callsigns,
+    /// Common words and on-air abbreviations.
+    Words,
+    /// Exchanges of a contact, with callsigns, reports, names and places.
+    Qso,
+}
+
+impl Material {
+    pub const ALL: [Self; 4] = [Self::Groups, Self::Callsigns, Self::Words, Self::Qso];
+
+    /// The name to show in a user interface.
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Groups => "Random Groups",
+            Self::Callsigns => "Callsigns",
+            Self::Words => "Words",
+            Self::Qso => "QSO",
+        }
+    }
+}
+
+/// One step of the alignment between sent text and its copy.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Edit {
+    /// The character was copied.
+    Match(char),
+    /// The character was copied as another one.
+    Substitute { sent: char, copied: char },
+    /// The character was not copied.
+    Missed(char),
+    /// A character was copied that was not sent.
+    Extra(char),
+}
+
+/// A step of an alignment, by the index of the sent and copied character
+/// it covers.
+#[derive(Debug, Clone, Copy)]
+struct Step {
+    sent: Option<usize>,
+    copied: Option<usize>,
+}
+
+fn same(a: char, b: char) -> bool {
+    a == b || a.to_uppercase().eq(b.to_uppercase())
+}
+
+/// The fewest insertions, deletions and substitutions that turn `sent`
+/// into `copied`, from the Levenshtein distance table.
+fn alignment(sent: &[char], copied: &[char]) -> Vec<Step> {
+    let width = copied.len() + 1;
+    let mut cost = vec![0_usize; (sent.len() + 1) * width];
+    for i in 0..=sent.len() {
+        for j in 0..=copied.len() {
+            cost[i * width + j] = match (i, j) {
+                (0, j) => j,
+                (i, 0) => i,
+                (i, j) => {
+                    let substitute = usize::from(!same(sent[i - 1], copied[j - 1]));
+                    (cost[(i - 1) * width + j - 1] + substitute)
+                        .min(cost[(i - 1) * width + j] + 1)
+                        .min(cost[i * width + j - 1] + 1)
+                }
+            };
+        }
+    }
+
+    let mut steps = Vec::new();
+    let (mut i, mut j) = (sent.len(), copied.len());
+    while i > 0 || j > 0 {
+        let here = cost[i * width + j];
+        if i > 0
+            && j > 0
+            && here
+                == cost[(i - 1) * width + j - 1] + usize::from(!same(sent[i - 1], copied[j - 1]))
+        {
+            i -= 1;
+            j -= 1;
+            steps.push(Step {
+                sent: Some(i),
+                copied: Some(j),
+            });
+        } else if i > 0 && here == cost[(i - 1) * width + j] + 1 {
+            i -= 1;
+            steps.push(Step {
+                sent: Some(i),
+                copied: None,
+            });
+        } else {
+            j -= 1;
+            steps.push(Step {
+                sent: None,
+                copied: Some(j),
+            });
+        }
+    }
+    steps.reverse();
+    steps
+}
+
+/// Aligns typed `copy` with the `sent` text, so a missed or extra character
+/// only counts once instead of shifting the rest of the copy. Letters match
+/// regardless of case.
+#[must_use]
+pub fn align(sent: &str, copy: &str) -> Vec<Edit> {
+    let sent: Vec<char> = sent.chars().collect();
+    let copied: Vec<char> = copy.chars().collect();
+    alignment(&sent, &copied)
+        .into_iter()
+        .map(|step| edit(step, &sent, &copied))
+        .collect()
+}
+
+fn edit(step: Step, sent: &[char], copied: &[char]) -> Edit {
+    match (step.sent.map(|i| sent[i]), step.copied.map(|j| copied[j])) {
+        (Some(sent), Some(copied)) if same(sent, copied) => Edit::Match(sent),
+        (Some(sent), Some(copied)) => Edit::Substitute { sent, copied },
+        (Some(sent), None) => Edit::Missed(sent),
+        (None, Some(copied)) => Edit::Extra(copied),
+        (None, None) => unreachable!("every step covers a character"),
+    }
+}
+
+/// How a character has fared over all practice sessions.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct CharacterStats {
+    sent: u32,
+    errors: u32,
+    /// The sum of the measured response times, in milliseconds.
+    latency_ms: u64,
+    /// How many response times were measured.
+    timed: u32,
+}
+
+impl CharacterStats {
+    /// How often the character was sent.
+    #[must_use]
+    pub const fn sent(&self) -> u32 {
+        self.sent
+    }
+
+    /// How often it was missed or miscopied.
+    #[must_use]
+    pub const fn errors(&self) -> u32 {
+        self.errors
+    }
+
+    /// The share of the times it was sent that it was not copied, from `0.0`
+    /// to `1.0`, or `None` if it was never sent.
+    #[must_use]
+    pub fn error_rate(&self) -> Option<f64> {
+        (self.sent > 0).then(|| f64::from(self.errors) / f64::from(self.sent))
+    }
+
+    /// The mean time from the end of the character to typing it, if any
+    /// were measured.
+    #[must_use]
+    pub fn mean_latency(&self) -> Option<Duration> {
+        (self.timed > 0).then(|| Duration::from_millis(self.latency_ms / u64::from(self.timed)))
+    }
+}
+
+/// Per-character error rates and response times, kept across sessions so
+/// practice can dwell on weak characters. Save them with
+/// [`Stats::to_json`] under [`STATS_KEY`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Stats {
+    characters: BTreeMap<char, CharacterStats>,
+}
+
+impl Stats {
+    /// Parses statistics saved by [`Stats::to_json`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Storage`] if `json` is not saved statistics.
+    pub fn from_json(json: &str) -> Result<Self> {
+        serde_json::from_str(json).map_err(|err| Error::Storage(err.to_string()))
+    }
+
+    /// The statistics as JSON, for saving.
+    #[must_use]
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+
+    /// The statistics of `char`, if it has been sent.
+    #[must_use]
+    pub fn character(&self, char: char) -> Option<&CharacterStats> {
+        self.characters.get(&char.to_ascii_uppercase())
+    }
+
+    /// The characters sent and their statistics, in character order.
+    pub fn iter(&self) -> impl Iterator<Item = (char, &CharacterStats)> {
+        self.characters.iter().map(|(&char, stats)| (char, stats))
+    }
+
+    /// How much more often than usual practice text should use `char`: `1.0`
+    /// for a character that is always copied quickly, up to `5.0` for one
+    /// that is always missed, plus up to two for slow copy. A character
+    /// never sent counts as missed half the time.
+    #[must_use]
+    pub fn weight(&self, char: char) -> f64 {
+        let stats = self.character(char).copied().unwrap_or_default();
+        // One imagined hit and one imagined miss keep a few lucky or unlucky
+        // copies from dominating.
+        let error_rate = f64::from(stats.errors + 1) / f64::from(stats.sent + 2);
+        let latency = stats
+            .mean_latency()
+            .map_or(0.0, |latency| latency.as_secs_f64().min(MAX_LATENCY_WEIGHT));
+        1.0 + 4.0 * error_rate + latency
+    }
+
+    fn record(&mut self, char: char, correct: bool, latency: Option<Duration>) {
+        let stats = self
+            .characters
+            .entry(char.to_ascii_uppercase())
+            .or_default();
+        stats.sent += 1;
+        stats.errors += u32::from(!correct);
+        if let Some(latency) = latency {
+            stats.latency_ms += u64::try_from(latency.as_millis()).unwrap_or(u64::MAX);
+            stats.timed += 1;
+        }
+    }
+}
+
+/// The outcome of copying one exercise.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Attempt {
+    edits: Vec<Edit>,
+}
+
+impl Attempt {
+    /// The alignment of the copy with the sent text.
+    #[must_use]
+    pub fn edits(&self) -> &[Edit] {
+        &self.edits
+    }
+
+    /// How many characters were sent, not counting spaces.
+    #[must_use]
+    pub fn sent(&self) -> usize {
+        self.edits
+            .iter()
+            .filter(|edit| match edit {
+                Edit::Match(sent) | Edit::Substitute { sent, .. } | Edit::Missed(sent) => {
+                    !sent.is_whitespace()
+                }
+                Edit::Extra(_) => false,
+            })
+            .count()
+    }
+
+    /// How many of the sent characters were copied.
+    #[must_use]
+    pub fn copied(&self) -> usize {
+        self.edits
+            .iter()
+            .filter(|edit| matches!(edit, Edit::Match(sent) if !sent.is_whitespace()))
+            .count()
+    }
+
+    /// The share of the sent characters that were copied, from `0.0` to
+    /// `1.0`.
+    #[must_use]
+    pub fn accuracy(&self) -> f64 {
+        match self.sent() {
+            0 => 1.0,
+            sent => self.copied() as f64 / sent as f64,
+        }
+    }
+}
+
+/// Generates practice text and keeps the statistics of the copy.
+///
+/// Each exercise is played with
+/// [`Backend::play_morse`](crate::backend::Backend::play_morse), typed by
+/// the user, and handed to [`Session::check`], which updates
+/// [`Session::stats`]. Characters with more errors or slower copy come up
+/// more often in later exercises.
+#[derive(Debug, Clone)]
+pub struct Session {
+    pub material: Material,
+    pub stats: Stats,
+    random: Random,
+    /// Generates callsigns by the prefix rules of their countries.
+    contest: Contest,
+}
+
+impl Session {
+    /// A session continuing from saved `stats`. The same `seed` gives the
+    /// same exercises.
+    #[must_use]
+    pub fn new(material: Material, stats: Stats, seed: u64) -> Self {
+        let mut random = Random::new(seed);
+        let contest = Contest::new(Exchange::default(), random.next());
+        Self {
+            material,
+            stats,
+            random,
+            contest,
+        }
+    }
+
+    /// The text of an exercise with `items` groups, callsigns, words or QSO
+    /// exchanges.
+    pub fn exercise(&mut self, items: usize) -> String {
+        let items: Vec<String> = (0..items)
+            .map(|_| match self.material {
+                Material::Groups => self.group(),
+                Material::Callsigns => self.callsign(),
+                Material::Words => self.word().to_string(),
+                Material::Qso => self.exchange(),
+            })
+            .collect();
+        items.join(" ")
+    }
+
+    /// A character of `characters`, favouring weak ones.
+    fn character(&mut self, characters: &str) -> char {
+        let characters: Vec<char> = characters.chars().collect();
+        let weights: Vec<f64> = characters
+            .iter()
+            .map(|&char| self.stats.weight(char))
+            .collect();
+        characters[self.random.weighted(&weights)]
+    }
+
+    fn group(&mut self) -> String {
+        (0..GROUP_LEN).map(|_| self.character(KOCH_ORDER)).collect()
+    }
+
+    /// A callsign of a contest station, favouring those with weak
+    /// characters.
+    fn callsign(&mut self) -> String {
+        let mut callsigns: Vec<String> = (0..CALLSIGN_CHOICES)
+            .map(|_| self.contest.station().callsign)
+            .collect();
+        let weights: Vec<f64> = callsigns
+            .iter()
+            .map(|callsign| {
+                let total: f64 = callsign.chars().map(|char| self.stats.weight(char)).sum();
+                total / callsign.len() as f64
+            })
+            .collect();
+        callsigns.swap_remove(self.random.weighted(&weights))
+    }
+
+    /// A word, favouring those with weak characters.
+    fn word(&mut self) -> &'static str {
+        let weights: Vec<f64> = WORDS
+            .iter()
+            .map(|word| {
+                let total: f64 = word.chars().map(|char| self.stats.weight(char)).sum();
+                total / word.len() as f64
+            })
+            .collect();
+        WORDS[self.random.weighted(&weights)]
+    }
+
+    fn exchange(&mut self) -> String {
+        let template = self.random.choose(QSO);
+        let to = self.callsign();
+        let from = self.callsign();
+        template
+            .replace("{to}", &to)
+            .replace("{from}", &from)
+            .replace("{rst}", self.random.choose(REPORTS))
+            .replace("{name}", self.random.choose(NAMES))
+            .replace("{qth}", self.random.choose(QTHS))
+            .replace("{wx}", self.random.choose(WEATHER))
+    }
+
+    /// Scores the `copy` of the `sent` exercise and adds it to the
+    /// statistics.
+    ///
+    /// `typed_at` holds, for each character of `copy`, the time from the
+    /// start of playback to when it was typed; it may be shorter than the
+    /// copy, or empty if typing was not timed. `options` and `timing` are
+    /// those the exercise was played with, and give the time each character
+    /// finished sounding, from which response times are measured.
+    pub fn check(
+        &mut self,
+        sent: &str,
+        copy: &str,
+        typed_at: &[Duration],
+        options: &Options,
+        timing: &Timing,
+    ) -> Attempt {
+        // When each character of the text finished sounding, by its byte
+        // offset. A character that does not encode has no Morse character
+        // of its own to end.
+        let mut ends: BTreeMap<usize, Duration> = BTreeMap::new();
+        for progress in schedule(&encode(sent, options), options, timing) {
+            if progress.kind == ElementKind::On
+                && let Some(range) = text_range(sent, progress.character, &Translation::default())
+            {
+                ends.insert(range.start, progress.start + progress.duration);
+            }
+        }
+
+        let offsets: Vec<usize> = sent.char_indices().map(|(offset, _)| offset).collect();
+        let sent: Vec<char> = sent.chars().collect();
+        let copied: Vec<char> = copy.chars().collect();
+        let mut edits = Vec::new();
+
+        for step in alignment(&sent, &copied) {
+            let edit = edit(step, &sent, &copied);
+            if let Some(i) = step.sent
+                && !sent[i].is_whitespace()
+            {
+                let correct = matches!(edit, Edit::Match(_));
+                let latency = step
+                    .copied
+                    .filter(|_| correct)
+                    .and_then(|j| typed_at.get(j))
+                    .zip(ends.get(&offsets[i]))
+                    .map(|(typed, end)| typed.saturating_sub(*end));
+                self.stats.record(sent[i], correct, latency);
+            }
+            edits.push(edit);
+        }
+
+        Attempt { edits }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn align_matches_regardless_of_case() {
+        assert_eq!(align("Cq", "cQ"), vec![Edit::Match('C'), Edit::Match('q')]);
+    }
+
+    #[test]
+    fn align_counts_each_mistake_once() {
+        assert_eq!(
+            align("PARIS", "PRIS"),
+            vec![
+                Edit::Match('P'),
+                Edit::Missed('A'),
+                Edit::Match('R'),
+                Edit::Match('I'),
+                Edit::Match('S'),
+            ]
+        );
+        assert_eq!(
+            align("TEST", "TEAST"),
+            vec![
+                Edit::Match('T'),
+                Edit::Match('E'),
+                Edit::Extra('A'),
+                Edit::Match('S'),
+                Edit::Match('T'),
+            ]
+        );
+        assert_eq!(
+            align("CQ", "CO"),
+            vec![
+                Edit::Match('C'),
+                Edit::Substitute {
+                    sent: 'Q',
+                    copied: 'O'
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn align_handles_empty_text() {
+        assert_eq!(align("", ""), vec![]);
+        assert_eq!(align("AB", ""), vec![Edit::Missed('A'), Edit::Missed('B')]);
+        assert_eq!(align("", "E"), vec![Edit::Extra('E')]);
+    }
+
+    #[test]
+    fn check_scores_and_records_the_copy() {
+        let mut session = Session::new(Material::Groups, Stats::default(), 1);
+        let attempt = session.check(
+            "AB CD",
+            "ab xd",
+            &[],
+            &Options::default(),
+            &Timing::default(),
+        );
+
+        assert_eq!((attempt.sent(), attempt.copied()), (4, 3));
+        assert_eq!(attempt.accuracy(), 0.75);
+        let c = session.stats.character('c').unwrap();
+        assert_eq!((c.sent(), c.errors()), (1, 1));
+        assert_eq!(session.stats.character('A').unwrap().errors(), 0);
+        assert!(session.stats.weight('C') > session.stats.weight('A'));
+    }
+
+    #[test]
+    fn latency_is_measured_from_each_characters_own_end() {
+        let mut session = Session::new(Material::Groups, Stats::default(), 1);
+        let typed_at = [Duration::from_secs(10); 3];
+        session.check(
+            "K\u{2603}M",
+            "K\u{2603}M",
+            &typed_at,
+            &Options::default(),
+            &Timing::from_wpm(20),
+        );
+
+        let latency = |char| session.stats.character(char).unwrap().mean_latency();
+        assert_eq!(latency('\u{2603}'), None);
+        let (k, m) = (latency('K').unwrap(), latency('M').unwrap());
+        assert!(k > m && m < Duration::from_secs(10));
+    }
+
+    #[test]
+    fn callsigns_come_from_contest_stations() {
+        let mut session = Session::new(Material::Callsigns, Stats::default(), 3);
+        for callsign in session.exercise(20).split(' ') {
+            assert!(callsign.chars().any(|char| char.is_ascii_digit()));
+            assert!(callsign.chars().all(|char| char.is_ascii_alphanumeric()));
+        }
+    }
+
+    #[test]
+    fn stats_survive_json() {
+        let mut session = Session::new(Material::Groups, Stats::default(), 1);
+        session.check("KM", "K", &[], &Options::default(), &Timing::default());
+
+        let json = session.stats.to_json();
+        assert_eq!(Stats::from_json(&json).unwrap(), session.stats);
+        assert!(Stats::from_json("not stats").is_err());
+    }
+}
@@ -0,0 +1,103 @@
+use std::{ops::Range, time::Duration};
+
+use morsify::Options;
+
+use crate::{
+    timeline::{ElementKind, timeline},
+    timing::Timing,
+};
+
+/// Where playback is in a message, reported as each element starts sounding.
+///
+/// Front ends use it to flash a light while the tone is keyed and to
+/// highlight the character being sent.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Progress {
+    /// Index of the element in the message's timeline.
+    pub element: usize,
+    /// Whether the tone is keyed during the element.
+    pub kind: ElementKind,
+    /// Index of the character the element belongs to, counting the
+    /// characters of the whole message from zero. A gap belongs to the
+    /// character before it.
+    pub character: usize,
+    /// Byte range of that character in the Morse code string.
+    pub morse: Range<usize>,
+    /// Time from the start of the message to the start of the element.
+    pub start: Duration,
+    pub duration: Duration,
+}
+
+/// The progress events of a message, one per element of its timeline, in
+/// playback order.
+#[must_use]
+pub fn schedule(morse_code: &str, options: &Options, timing: &Timing) -> Vec<Progress> {
+    let mut symbols = symbols(morse_code, options);
+    let mut current = (0, 0..0);
+    let mut start = Duration::ZERO;
+
+    timeline(morse_code, options, timing)
+        .into_iter()
+        .enumerate()
+        .map(|(element, item)| {
+            // Every tone in the timeline is one dot or dash of the string.
+            if item.kind == ElementKind::On
+                && let Some((character, offset)) = symbols.next()
+            {
+                current = (character, character_range(morse_code, options, offset));
+            }
+
+            let progress = Progress {
+                element,
+                kind: item.kind,
+                character: current.0,
+                morse: current.1.clone(),
+                start,
+                duration: item.duration,
+            };
+            start += item.duration;
+            progress
+        })
+        .collect()
+}
+
+/// The dots and dashes of `morse_code`, each with the index of its
+/// character and its byte offset.
+fn symbols<'a>(
+    morse_code: &'a str,
+    options: &'a Options,
+) -> impl Iterator<Item = (usize, usize)> + 'a {
+    let is_symbol = |char| char == options.dot || char == options.dash;
+    let mut characters = 0;
+    let mut in_character = false;
+
+    morse_code.char_indices().filter_map(move |(offset, char)| {
+        if !is_symbol(char) {
+            in_character = false;
+            return None;
+        }
+        if !in_character {
+            in_character = true;
+            characters += 1;
+        }
+        Some((characters - 1, offset))
+    })
+}
+
+/// The byte range of the run of dots and dashes containing `offset`.
+fn character_range(morse_code: &str, options: &Options, offset: usize) -> Range<usize> {
+    let is_symbol = |char| char == options.dot || char == options.dash;
+
+    let start = morse_code[..offset]
+        .char_indices()
+        .rev()
+        .take_while(|&(_, char)| is_symbol(char))
+        .last()
+        .map_or(offset, |(index, _)| index);
+    let end = morse_code[offset..]
+        .char_indices()
+        .find(|&(_, char)| !is_symbol(char))
+        .map_or(morse_code.len(), |(index, _)| offset + index);
+
+    start..end
+}
@@ -0,0 +1,55 @@
+/// A small xorshift generator; lessons and practice text only need to look
+/// random.
+#[derive(Debug, Clone)]
+pub(crate) struct Random(u64);
+
+impl Random {
+    pub(crate) fn new(seed: u64) -> Self {
+        // Xorshift never leaves zero.
+        Self(if seed == 0 {
+            0x9E37_79B9_7F4A_7C15
+        } else {
+            seed
+        })
+    }
+
+    /// The next 64 random bits, such as a seed for another generator.
+    pub(crate) fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    /// A number below `bound`, which must not be zero.
+    pub(crate) fn below(&mut self, bound: usize) -> usize {
+        (self.next() % bound as u64) as usize
+    }
+
+    /// A number in `0.0..1.0`.
+    pub(crate) fn fraction(&mut self) -> f64 {
+        (self.next() >> 11) as f64 / (1_u64 << 53) as f64
+    }
+
+    /// An index into `weights`, picked in proportion to its weight. Weights
+    /// must be finite and not negative, and at least one must be positive.
+    pub(crate) fn weighted(&mut self, weights: &[f64]) -> usize {
+        let total: f64 = weights.iter().sum();
+        let mut target = self.fraction() * total;
+        for (index, &weight) in weights.iter().enumerate() {
+            if target < weight {
+                return index;
+            }
+            target -= weight;
+        }
+        weights
+            .iter()
+            .rposition(|&weight| weight > 0.0)
+            .unwrap_or(0)
+    }
+
+    /// One of `items`, which must not be empty, picked evenly.
+    pub(crate) fn choose<T: Copy>(&mut self, items: &[T]) -> T {
+        items[self.below(items.len())]
+    }
+}
@@ -0,0 +1,153 @@
+use std::time::Duration;
+
+use morsify::Options;
+
+use crate::timing::Timing;
+
+/// Whether the tone is keyed during an [`Element`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ElementKind {
+    On,
+    Off,
+}
+
+/// A stretch of tone or silence in a Morse timeline.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Element {
+    pub kind: ElementKind,
+    pub duration: Duration,
+}
+
+impl Element {
+    #[must_use]
+    pub const fn on(duration: Duration) -> Self {
+        Self {
+            kind: ElementKind::On,
+            duration,
+        }
+    }
+
+    #[must_use]
+    pub const fn off(duration: Duration) -> Self {
+        Self {
+            kind: ElementKind::Off,
+            duration,
+        }
+    }
+}
+
+/// The widest boundary seen since the last dot or dash.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Gap {
+    Element,
+    Character,
+    Word,
+}
+
+/// Turns a Morse code string into the sequence of tones and silences that
+/// sound it.
+///
+/// `options.dot` and `options.dash` become tones, `options.separator` ends a
+/// character and `options.space` ends a word. Any other character is treated
+/// as a word boundary as well. Runs of boundaries collapse into the widest
+/// one, so `". / ."` has a single word gap, and the timeline neither starts
+/// nor ends with silence.
+#[must_use]
+pub fn timeline(morse_code: &str, options: &Options, timing: &Timing) -> Vec<Element> {
+    let mut elements = Vec::new();
+    let mut gap = None;
+
+    for char in morse_code.chars() {
+        let tone = match char {
+            c if c == options.dot => timing.dit(),
+            c if c == options.dash => timing.dah(),
+            c if c == options.separator => {
+                gap = gap.max(Some(Gap::Character));
+                continue;
+            }
+            _ => {
+                gap = Some(Gap::Word);
+                continue;
+            }
+        };
+
+        if !elements.is_empty() {
+            let silence = match gap.unwrap_or(Gap::Element) {
+                Gap::Element => timing.intra_character(),
+                Gap::Character => timing.inter_character(),
+                Gap::Word => timing.inter_word(),
+            };
+            elements.push(Element::off(silence));
+        }
+        elements.push(Element::on(tone));
+        gap = None;
+    }
+
+    elements
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options() -> Options {
+        Options {
+            dot: '.',
+            dash: '-',
+            separator: ' ',
+            space: '/',
+            ..Options::default()
+        }
+    }
+
+    fn total(elements: &[Element]) -> Duration {
+        elements.iter().map(|element| element.duration).sum()
+    }
+
+    #[test]
+    fn paris_is_fifty_units() {
+        let timing = Timing::from_wpm(20);
+        let elements = timeline(".--. .- .-. .. ...", &options(), &timing);
+
+        // The timeline stops at the last element, so the trailing word gap
+        // makes up the rest of the 50 units.
+        assert_eq!(total(&elements) + timing.inter_word(), timing.dit() * 50);
+        assert_eq!(elements.len(), 2 * 14 - 1);
+    }
+
+    #[test]
+    fn farnsworth_stretches_only_the_gaps() {
+        let timing = Timing::farnsworth(20, 10);
+        let elements = timeline(".--. .- .-. .. ...", &options(), &timing);
+
+        assert_eq!(timing.dit(), Timing::from_wpm(20).dit());
+        let word = total(&elements) + timing.inter_word();
+        let expected = Duration::from_secs(6);
+        assert!(word.abs_diff(expected) < Duration::from_micros(1), "{word:?}");
+    }
+
+    #[test]
+    fn gaps_follow_the_widest_boundary() {
+        let timing = Timing::from_wpm(20);
+        let elements = timeline(" .. . / . ", &options(), &timing);
+
+        assert_eq!(
+            elements,
+            [
+                Element::on(timing.dit()),
+                Element::off(timing.intra_character()),
+                Element::on(timing.dit()),
+                Element::off(timing.inter_character()),
+                Element::on(timing.dit()),
+                Element::off(timing.inter_word()),
+                Element::on(timing.dit()),
+            ]
+        );
+    }
+
+    #[test]
+    fn empty_input_has_no_elements() {
+        assert!(timeline("", &options(), &Timing::default()).is_empty());
+        assert!(timeline(" / ", &options(), &Timing::default()).is_empty());
+    }
+}
@@ -0,0 +1,106 @@
+use std::time::Duration;
+
+/// Number of dit units in the standard word "PARIS ", including its trailing
+/// word gap.
+const PARIS_UNITS: f64 = 50.0;
+
+/// Number of those units that fall inside characters; the remaining 19 are the
+/// four inter-character gaps and the word gap, which Farnsworth timing
+/// stretches.
+const PARIS_CHARACTER_UNITS: f64 = 31.0;
+
+/// Element and gap durations derived from a sending speed.
+///
+/// Speeds are in words per minute using the PARIS standard, where one word is
+/// 50 dit units long. With Farnsworth timing, characters are sent at the
+/// character speed while the gaps between characters and words are stretched
+/// so the overall text arrives at the slower effective speed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Timing {
+    dit: Duration,
+    inter_character: Duration,
+    inter_word: Duration,
+}
+
+impl Timing {
+    /// Standard timing, where characters and gaps use the same speed.
+    ///
+    /// A speed of zero is treated as 1 WPM.
+    #[must_use]
+    pub fn from_wpm(wpm: u32) -> Self {
+        let dit = Self::dit_for(wpm);
+
+        Self {
+            dit,
+            inter_character: dit * 3,
+            inter_word: dit * 7,
+        }
+    }
+
+    /// Farnsworth timing: characters are sent at `character_wpm` and the gaps
+    /// are lengthened until the text averages `effective_wpm`.
+    ///
+    /// If `effective_wpm` is not slower than `character_wpm` this is the same
+    /// as [`Timing::from_wpm`]. Speeds of zero are treated as 1 WPM.
+    #[must_use]
+    pub fn farnsworth(character_wpm: u32, effective_wpm: u32) -> Self {
+        let character_wpm = character_wpm.max(1);
+        let effective_wpm = effective_wpm.max(1);
+        if effective_wpm >= character_wpm {
+            return Self::from_wpm(character_wpm);
+        }
+
+        let dit = Self::dit_for(character_wpm);
+        let word = Duration::from_secs_f64(60.0 / f64::from(effective_wpm));
+        let gap_unit = word
+            .saturating_sub(dit.mul_f64(PARIS_CHARACTER_UNITS))
+            .div_f64(PARIS_UNITS - PARIS_CHARACTER_UNITS);
+
+        Self {
+            dit,
+            inter_character: gap_unit * 3,
+            inter_word: gap_unit * 7,
+        }
+    }
+
+    fn dit_for(wpm: u32) -> Duration {
+        Duration::from_secs_f64(60.0 / (PARIS_UNITS * f64::from(wpm.max(1))))
+    }
+
+    /// Length of a dit.
+    #[must_use]
+    pub const fn dit(&self) -> Duration {
+        self.dit
+    }
+
+    /// Length of a dah, three dits.
+    #[must_use]
+    pub fn dah(&self) -> Duration {
+        self.dit * 3
+    }
+
+    /// Silence between the elements of one character, one dit.
+    #[must_use]
+    pub const fn intra_character(&self) -> Duration {
+        self.dit
+    }
+
+    /// Silence between two characters of a word.
+    #[must_use]
+    pub const fn inter_character(&self) -> Duration {
+        self.inter_character
+    }
+
+    /// Silence between two words.
+    #[must_use]
+    pub const fn inter_word(&self) -> Duration {
+        self.inter_word
+    }
+}
+
+impl Default for Timing {
+    /// 20 WPM without Farnsworth spacing.
+    fn default() -> Self {
+        Self::from_wpm(20)
+    }
+}
@@ -0,0 +1,184 @@
+use std::{f64::consts::TAU, time::Duration};
+
+use crate::timeline::{Element, ElementKind};
+
+/// Shape of the attack and release ramps applied to every tone.
+///
+/// Keying a sine wave on and off instantly produces broadband clicks;
+/// transmitters shape the edges of each element, and so does the
+/// synthesizer.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Envelope {
+    /// A half cosine from silence to full amplitude, the usual CW keying shape.
+    #[default]
+    RaisedCosine,
+    /// A straight ramp.
+    Linear,
+}
+
+impl Envelope {
+    /// The gain at `progress` through a rise, from `0.0` (silent) to `1.0`
+    /// (full amplitude). Releases use the same curve reversed.
+    #[must_use]
+    pub fn gain(self, progress: f32) -> f32 {
+        let progress = progress.clamp(0.0, 1.0);
+        match self {
+            Self::RaisedCosine => 0.5 - 0.5 * (std::f32::consts::PI * progress).cos(),
+            Self::Linear => progress,
+        }
+    }
+
+    /// The rise sampled at `len` evenly spaced points, including both ends.
+    #[must_use]
+    pub fn curve(self, len: usize) -> Vec<f32> {
+        let last = len.saturating_sub(1).max(1) as f32;
+        (0..len).map(|i| self.gain(i as f32 / last)).collect()
+    }
+}
+
+/// Pitch and keying shape of the sidetone.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Tone {
+    /// Frequency in hertz.
+    pub frequency: f32,
+    /// Time the envelope takes to go from silence to full amplitude, and back.
+    /// Elements shorter than two rise times get a proportionally shorter
+    /// ramp.
+    pub rise_time: Duration,
+    pub envelope: Envelope,
+}
+
+impl Tone {
+    /// A tone at `frequency` with the default 5 ms raised-cosine envelope.
+    #[must_use]
+    pub fn new(frequency: f32) -> Self {
+        Self {
+            frequency,
+            ..Self::default()
+        }
+    }
+
+    /// The rise time actually used for an element of `duration`.
+    #[must_use]
+    pub fn rise_time_for(&self, duration: Duration) -> Duration {
+        self.rise_time.min(duration / 2)
+    }
+}
+
+impl Default for Tone {
+    /// 700 Hz with a 5 ms raised-cosine envelope.
+    fn default() -> Self {
+        Self {
+            frequency: 700.0,
+            rise_time: Duration::from_millis(5),
+            envelope: Envelope::default(),
+        }
+    }
+}
+
+/// Mono samples, in `-1.0..=1.0`, synthesized lazily from a timeline.
+///
+/// The oscillator runs continuously across the whole timeline, so every
+/// element starts at the phase the previous one left off.
+#[derive(Debug, Clone)]
+pub struct Synth {
+    elements: std::vec::IntoIter<Element>,
+    tone: Tone,
+    sample_rate: u32,
+    phase: f64,
+    current: Option<ElementSamples>,
+    remaining: usize,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct ElementSamples {
+    kind: ElementKind,
+    len: usize,
+    ramp: usize,
+    position: usize,
+}
+
+impl Synth {
+    #[must_use]
+    pub fn new(elements: Vec<Element>, tone: Tone, sample_rate: u32) -> Self {
+        let sample_rate = sample_rate.max(1);
+        let remaining = elements
+            .iter()
+            .map(|element| samples_for(element.duration, sample_rate))
+            .sum();
+
+        Self {
+            elements: elements.into_iter(),
+            tone,
+            sample_rate,
+            phase: 0.0,
+            current: None,
+            remaining,
+        }
+    }
+
+    #[must_use]
+    pub const fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    fn next_element(&mut self) -> Option<ElementSamples> {
+        loop {
+            let element = self.elements.next()?;
+            let len = samples_for(element.duration, self.sample_rate);
+            if len == 0 {
+                continue;
+            }
+
+            let ramp = samples_for(self.tone.rise_time_for(element.duration), self.sample_rate);
+            return Some(ElementSamples {
+                kind: element.kind,
+                len,
+                ramp,
+                position: 0,
+            });
+        }
+    }
+}
+
+impl Iterator for Synth {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        let mut current = match self.current.take() {
+            Some(current) if current.position < current.len => current,
+            _ => self.next_element()?,
+        };
+
+        let sample = match current.kind {
+            ElementKind::On => {
+                let edge = current.position.min(current.len - 1 - current.position);
+                let gain = if edge < current.ramp {
+                    self.tone.envelope.gain(edge as f32 / current.ramp as f32)
+                } else {
+                    1.0
+                };
+                gain * self.phase.sin() as f32
+            }
+            ElementKind::Off => 0.0,
+        };
+
+        self.phase =
+            (self.phase + TAU * f64::from(self.tone.frequency) / f64::from(self.sample_rate)) % TAU;
+        current.position += 1;
+        self.current = Some(current);
+        self.remaining = self.remaining.saturating_sub(1);
+
+        Some(sample)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl ExactSizeIterator for Synth {}
+
+fn samples_for(duration: Duration, sample_rate: u32) -> usize {
+    (duration.as_secs_f64() * f64::from(sample_rate)).round() as usize
+}
@@ -0,0 +1,320 @@
+use std::collections::BTreeMap;
+
+use crate::{Error, Result, random::Random, timing::Timing};
+
+/// The order the Koch method introduces characters in, as used by most
+/// training courses.
+pub const KOCH_ORDER: &str = "KMURESNAPTLWI.JZ=FOY,VG5/Q92H38B?47C1D60X";
+
+/// The setting the level of [`Koch`] training is saved under with
+/// [`Backend::write_setting`](crate::backend::Backend::write_setting).
+pub const LEVEL_KEY: &str = "koch-level";
+
+/// The share of characters copied correctly that unlocks the next one.
+pub const PASS_ACCURACY: f64 = 0.9;
+
+/// The number of characters in a group.
+pub(crate) const GROUP_LEN: usize = 5;
+
+/// Koch-method training: characters are sent at full speed from the first
+/// lesson, two to begin with, and one more is added each time a lesson is
+/// copied with at least [`PASS_ACCURACY`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Koch {
+    level: usize,
+    /// The speed characters are sent at.
+    pub character_wpm: u32,
+    /// The overall speed, reached with Farnsworth spacing between
+    /// characters and groups.
+    pub effective_wpm: u32,
+}
+
+impl Default for Koch {
+    /// The first lesson, with characters at 20 WPM spaced out to 10 WPM.
+    fn default() -> Self {
+        Self::new(2)
+    }
+}
+
+impl Koch {
+    /// Training with the first `level` characters of [`KOCH_ORDER`]
+    /// unlocked, clamped to at least two and at most all of them.
+    #[must_use]
+    pub fn new(level: usize) -> Self {
+        Self {
+            level: level.clamp(2, KOCH_ORDER.len()),
+            character_wpm: 20,
+            effective_wpm: 10,
+        }
+    }
+
+    /// The number of characters unlocked.
+    #[must_use]
+    pub const fn level(&self) -> usize {
+        self.level
+    }
+
+    /// Training at the level saved by [`Koch::to_setting`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Storage`] if `setting` is not a saved level.
+    pub fn from_setting(setting: &str) -> Result<Self> {
+        setting
+            .trim()
+            .parse()
+            .map(Self::new)
+            .map_err(|err| Error::Storage(format!("invalid Koch level {setting:?}: {err}")))
+    }
+
+    /// The level, for saving under [`LEVEL_KEY`].
+    #[must_use]
+    pub fn to_setting(&self) -> String {
+        self.level.to_string()
+    }
+
+    /// The characters unlocked, in the order they were introduced.
+    #[must_use]
+    pub fn characters(&self) -> &'static str {
+        &KOCH_ORDER[..self.level]
+    }
+
+    /// The character introduced last, which a lesson is most likely to
+    /// trip over.
+    #[must_use]
+    pub fn newest(&self) -> char {
+        char::from(KOCH_ORDER.as_bytes()[self.level - 1])
+    }
+
+    /// Whether every character of [`KOCH_ORDER`] is unlocked.
+    #[must_use]
+    pub const fn is_complete(&self) -> bool {
+        self.level == KOCH_ORDER.len()
+    }
+
+    /// Character timing at full speed with Farnsworth spacing.
+    #[must_use]
+    pub fn timing(&self) -> Timing {
+        Timing::farnsworth(self.character_wpm, self.effective_wpm)
+    }
+
+    /// A lesson of `groups` random groups of five unlocked characters. The
+    /// same `seed` gives the same lesson.
+    #[must_use]
+    pub fn lesson(&self, groups: usize, seed: u64) -> Lesson {
+        let characters = self.characters().as_bytes();
+        let mut random = Random::new(seed);
+        let groups = (0..groups)
+            .map(|_| {
+                (0..GROUP_LEN)
+                    .map(|_| char::from(characters[random.below(characters.len())]))
+                    .collect()
+            })
+            .collect();
+        Lesson { groups }
+    }
+
+    /// Unlocks the next character if `score` passes, returning it.
+    pub fn advance(&mut self, score: &Score) -> Option<char> {
+        if !score.passed() || self.is_complete() {
+            return None;
+        }
+        self.level += 1;
+        Some(self.newest())
+    }
+}
+
+/// Groups of characters to send and copy.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Lesson {
+    groups: Vec<String>,
+}
+
+impl Lesson {
+    /// The groups to send, in order.
+    #[must_use]
+    pub fn groups(&self) -> &[String] {
+        &self.groups
+    }
+
+    /// The groups as text, separated by spaces, ready to encode.
+    #[must_use]
+    pub fn text(&self) -> String {
+        self.groups.join(" ")
+    }
+
+    /// Scores typed `copy` against the lesson. Groups are compared in
+    /// order, and characters by their position in the group, so a missed
+    /// character only costs its own group. Letters match regardless of
+    /// case.
+    #[must_use]
+    pub fn score(&self, copy: &str) -> Score {
+        let mut copied = copy.split_whitespace();
+        let mut score = Score::default();
+        for group in &self.groups {
+            let mut copied = copied.next().unwrap_or_default().chars();
+            for sent in group.chars() {
+                let correct = copied
+                    .next()
+                    .is_some_and(|char| char.eq_ignore_ascii_case(&sent));
+                score.record(sent, correct);
+            }
+        }
+        score
+    }
+}
+
+/// How often a character was sent, and copied correctly.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Tally {
+    pub sent: usize,
+    pub correct: usize,
+}
+
+impl Tally {
+    /// The share copied correctly, from `0.0` to `1.0`, or `None` if
+    /// nothing was sent.
+    #[must_use]
+    pub fn accuracy(&self) -> Option<f64> {
+        (self.sent > 0).then(|| self.correct as f64 / self.sent as f64)
+    }
+}
+
+/// The result of copying a lesson, per character.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Score {
+    characters: BTreeMap<char, Tally>,
+}
+
+impl Score {
+    fn record(&mut self, char: char, correct: bool) {
+        let tally = self.characters.entry(char).or_default();
+        tally.sent += 1;
+        tally.correct += usize::from(correct);
+    }
+
+    /// The tally of `char`, if it was sent.
+    #[must_use]
+    pub fn character(&self, char: char) -> Option<Tally> {
+        self.characters.get(&char).copied()
+    }
+
+    /// The tallies of the characters sent, in character order.
+    pub fn iter(&self) -> impl Iterator<Item = (char, Tally)> + '_ {
+        self.characters.iter().map(|(&char, &tally)| (char, tally))
+    }
+
+    /// The tally over all characters.
+    #[must_use]
+    pub fn total(&self) -> Tally {
+        self.characters
+            .values()
+            .fold(Tally::default(), |total, tally| Tally {
+                sent: total.sent + tally.sent,
+                correct: total.correct + tally.correct,
+            })
+    }
+
+    /// Whether at least [`PASS_ACCURACY`] of the characters were copied.
+    #[must_use]
+    pub fn passed(&self) -> bool {
+        self.total()
+            .accuracy()
+            .is_some_and(|accuracy| accuracy >= PASS_ACCURACY)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A lesson of one group of ten `char`s, and its copy with `correct`
+    /// of them right.
+    fn copied(char: char, correct: usize) -> (Lesson, String) {
+        let lesson = Lesson {
+            groups: vec![char.to_string().repeat(10)],
+        };
+        let copy = char.to_string().repeat(correct) + &"#".repeat(10 - correct);
+        (lesson, copy)
+    }
+
+    #[test]
+    fn lessons_use_only_unlocked_characters() {
+        let koch = Koch::default();
+        assert_eq!(koch.characters(), "KM");
+        assert_eq!(koch.newest(), 'M');
+        let lesson = koch.lesson(4, 1);
+        assert_eq!(lesson.groups().len(), 4);
+        assert!(lesson.groups().iter().all(|group| group.len() == GROUP_LEN));
+        assert!(lesson.text().chars().all(|char| "KM ".contains(char)));
+    }
+
+    #[test]
+    fn score_counts_each_character() {
+        let lesson = Lesson {
+            groups: vec!["KMKMK".to_string(), "MMKKM".to_string()],
+        };
+        let score = lesson.score("kmkmm MKK");
+        assert_eq!(
+            score.character('K'),
+            Some(Tally {
+                sent: 5,
+                correct: 3
+            })
+        );
+        assert_eq!(
+            score.character('M'),
+            Some(Tally {
+                sent: 5,
+                correct: 3
+            })
+        );
+        assert_eq!(
+            score.total(),
+            Tally {
+                sent: 10,
+                correct: 6
+            }
+        );
+        assert_eq!(score.character('U'), None);
+    }
+
+    #[test]
+    fn advances_at_pass_accuracy() {
+        let mut koch = Koch::default();
+        let (lesson, copy) = copied('K', 9);
+        assert_eq!(koch.advance(&lesson.score(&copy)), Some('U'));
+        assert_eq!(koch.level(), 3);
+    }
+
+    #[test]
+    fn stays_below_pass_accuracy() {
+        // 89 of 100 characters.
+        let lesson = Lesson {
+            groups: vec!["K".repeat(100)],
+        };
+        let copy = "K".repeat(89) + &"#".repeat(11);
+        let mut koch = Koch::default();
+        assert!(!lesson.score(&copy).passed());
+        assert_eq!(koch.advance(&lesson.score(&copy)), None);
+        assert_eq!(koch.level(), 2);
+    }
+
+    #[test]
+    fn no_advance_past_the_last_character() {
+        let mut koch = Koch::new(usize::MAX);
+        assert!(koch.is_complete());
+        assert_eq!(koch.newest(), 'X');
+        let (lesson, copy) = copied('X', 10);
+        assert_eq!(koch.advance(&lesson.score(&copy)), None);
+        assert_eq!(koch.level(), KOCH_ORDER.len());
+    }
+
+    #[test]
+    fn level_survives_the_setting() {
+        let koch = Koch::new(7);
+        assert_eq!(Koch::from_setting(&koch.to_setting()).unwrap(), koch);
+        assert_eq!(Koch::from_setting("0").unwrap().level(), 2);
+        assert!(Koch::from_setting("many").is_err());
+    }
+}
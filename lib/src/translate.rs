@@ -0,0 +1,368 @@
+use std::ops::Range;
+
+use morsify::{MorseCode, Options};
+
+use crate::alphabet::{Alphabet, Table};
+
+/// The prosigns [`Translation::prosigns`] reads and writes, by the letters
+/// they are written with.
+const PROSIGNS: [&str; 5] = ["AR", "SK", "BT", "KN", "SOS"];
+
+/// How text maps to Morse code, beyond the symbols in [`Options`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Translation {
+    /// Reads the prosigns `<AR>`, `<SK>`, `<BT>`, `<KN>` and `<SOS>` in text
+    /// as one character, sending their letters without the gap between
+    /// characters, and decodes their patterns back to that notation. Other
+    /// letters in angle brackets are sent as they are. The patterns of `<AR>`, `<BT>` and `<KN>` then no longer
+    /// decode to `+`, `=` and `(`.
+    pub prosigns: bool,
+    /// Extra or overriding characters, which take precedence over the
+    /// built-in alphabet. [`Table::check_alphabet`] tells whether they clash
+    /// with the letters of `alphabet`.
+    pub table: Option<Table>,
+    /// The script of the text, whose letters are sent after those in
+    /// `table`.
+    pub alphabet: Alphabet,
+}
+
+impl Translation {
+    /// Whether `morsify` alone can translate, without the word-based path.
+    fn is_standard(&self) -> bool {
+        !self.prosigns && self.table.is_none() && self.alphabet == Alphabet::Latin
+    }
+}
+
+/// The symbols `morsify` encodes to and decodes from.
+fn standard_options() -> Options {
+    Options {
+        dot: '.',
+        dash: '-',
+        space: '/',
+        separator: ' ',
+        ..Default::default()
+    }
+}
+
+/// Rewrites Morse code from the symbols of `from` to those of `to`.
+fn convert(morse: &str, from: &Options, to: &Options) -> String {
+    morse
+        .chars()
+        .map(|char| match char {
+            c if c == from.dot => to.dot,
+            c if c == from.dash => to.dash,
+            c if c == from.space => to.space,
+            c if c == from.separator => to.separator,
+            c => c,
+        })
+        .collect()
+}
+
+/// Translates text to Morse code written with the symbols in `options`.
+#[must_use]
+pub fn encode(text: &str, options: &Options) -> String {
+    encode_with(text, options, &Translation::default())
+}
+
+/// Translates text to Morse code like [`encode`], following `translation`.
+#[must_use]
+pub fn encode_with(text: &str, options: &Options, translation: &Translation) -> String {
+    let morse = if translation.is_standard() {
+        MorseCode::default().encode(text)
+    } else {
+        encode_words(text, translation)
+    };
+    convert(&morse, &standard_options(), options)
+}
+
+/// Translates Morse code written with the symbols in `options` to text.
+#[must_use]
+pub fn decode(morse: &str, options: &Options) -> String {
+    decode_with(morse, options, &Translation::default())
+}
+
+/// Translates Morse code to text like [`decode`], following `translation`.
+#[must_use]
+pub fn decode_with(morse: &str, options: &Options, translation: &Translation) -> String {
+    let morse = convert(morse, options, &standard_options());
+    if translation.is_standard() {
+        MorseCode::default().decode(morse)
+    } else {
+        decode_words(&morse, translation)
+    }
+}
+
+/// The length in bytes of the prosign written as `<AR>` at the start of
+/// `text`, if it starts with one of [`PROSIGNS`].
+fn prosign_len(text: &str) -> Option<usize> {
+    let letters = text.strip_prefix('<')?;
+    let end = letters.find('>')?;
+    let known = PROSIGNS
+        .iter()
+        .any(|name| name.eq_ignore_ascii_case(&letters[..end]));
+    known.then_some(end + 2)
+}
+
+/// A run of plain characters in a word of text, or the letters of a
+/// prosign.
+enum Part<'a> {
+    Plain(&'a str),
+    Prosign(&'a str),
+}
+
+/// Splits a word of text at the prosigns written in it, if `prosigns` is
+/// set.
+fn parts(word: &str, prosigns: bool) -> Vec<Part<'_>> {
+    if !prosigns {
+        return vec![Part::Plain(word)];
+    }
+
+    let mut parts = Vec::new();
+    let mut plain = 0;
+    let mut index = 0;
+
+    while index < word.len() {
+        if let Some(len) = prosign_len(&word[index..]) {
+            if plain < index {
+                parts.push(Part::Plain(&word[plain..index]));
+            }
+            parts.push(Part::Prosign(&word[index + 1..index + len - 1]));
+            index += len;
+            plain = index;
+        } else {
+            index += word[index..].chars().next().map_or(1, char::len_utf8);
+        }
+    }
+    if plain < word.len() {
+        parts.push(Part::Plain(&word[plain..]));
+    }
+
+    parts
+}
+
+/// The standard Morse code of a prosign's letters, run together into one
+/// character.
+fn prosign_pattern(letters: &str) -> String {
+    let code = MorseCode::default();
+    letters
+        .chars()
+        .map(|letter| code.encode(letter.to_string()).trim().to_string())
+        .collect()
+}
+
+/// The patterns of one character of text, taken from the translation's
+/// table, then its alphabet, then the built-in alphabet.
+fn encode_char(char: char, translation: &Translation) -> Vec<String> {
+    if let Some(pattern) = translation
+        .table
+        .as_ref()
+        .and_then(|table| table.pattern(char))
+    {
+        return vec![pattern.to_string()];
+    }
+    match translation.alphabet.encode(char) {
+        Some(patterns) => patterns.into_iter().map(String::from).collect(),
+        None => vec![
+            MorseCode::default()
+                .encode(char.to_string())
+                .trim()
+                .to_string(),
+        ],
+    }
+}
+
+/// Encodes plain text one character at a time.
+fn encode_plain(plain: &str, translation: &Translation) -> String {
+    let characters: Vec<String> = plain
+        .chars()
+        .flat_map(|char| encode_char(char, translation))
+        .collect();
+    characters.join(" ")
+}
+
+/// Encodes text word by word, for the extensions `morsify` lacks.
+fn encode_words(text: &str, translation: &Translation) -> String {
+    let words: Vec<String> = text
+        .split_whitespace()
+        .map(|word| {
+            let characters: Vec<String> = parts(word, translation.prosigns)
+                .into_iter()
+                .map(|part| match part {
+                    Part::Plain(plain) => encode_plain(plain, translation),
+                    Part::Prosign(letters) => prosign_pattern(letters),
+                })
+                .collect();
+            characters.join(" ")
+        })
+        .collect();
+    words.join(" / ")
+}
+
+/// Decodes Morse code word by word, for the extensions `morsify` lacks.
+fn decode_words(morse: &str, translation: &Translation) -> String {
+    let code = MorseCode::default();
+    let prosigns: Vec<(String, &str)> = if translation.prosigns {
+        PROSIGNS
+            .iter()
+            .map(|name| (prosign_pattern(name), *name))
+            .collect()
+    } else {
+        Vec::new()
+    };
+    let decode_special = |character: &str| {
+        if let Some((_, name)) = prosigns.iter().find(|(pattern, _)| pattern == character) {
+            return Some(format!("<{name}>"));
+        }
+        translation
+            .table
+            .as_ref()
+            .and_then(|table| table.character(character))
+            .or_else(|| translation.alphabet.decode(character))
+            .map(String::from)
+    };
+
+    let words: Vec<String> = morse
+        .split('/')
+        .map(|word| {
+            let mut text = String::new();
+            let mut plain: Vec<&str> = Vec::new();
+            for character in word.split_whitespace() {
+                match decode_special(character) {
+                    Some(special) => {
+                        if !plain.is_empty() {
+                            text.push_str(&code.decode(plain.join(" ")));
+                            plain.clear();
+                        }
+                        text.push_str(&special);
+                    }
+                    None => plain.push(character),
+                }
+            }
+            if !plain.is_empty() {
+                text.push_str(&code.decode(plain.join(" ")));
+            }
+            translation.alphabet.compose(&text)
+        })
+        .filter(|word| !word.is_empty())
+        .collect();
+    words.join(" ")
+}
+
+/// The byte range in `text` of the character that the `character`th Morse
+/// character of its encoding stands for, as in [`Progress::character`].
+///
+/// [`Progress::character`]: crate::progress::Progress::character
+#[must_use]
+pub fn text_range(text: &str, character: usize, translation: &Translation) -> Option<Range<usize>> {
+    let mut index = 0;
+    let mut count = 0;
+
+    while let Some(char) = text[index..].chars().next() {
+        let (len, width) = match prosign_len(&text[index..]) {
+            Some(len) if translation.prosigns => (len, 1),
+            // A character that does not encode sounds no Morse character.
+            _ => {
+                let patterns = encode_char(char, translation);
+                let sounded = patterns
+                    .iter()
+                    .filter(|pattern| pattern.contains(['.', '-']))
+                    .count();
+                (char.len_utf8(), sounded)
+            }
+        };
+        if !char.is_whitespace() {
+            if (count..count + width).contains(&character) {
+                return Some(index..index + len);
+            }
+            count += width;
+        }
+        index += len;
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn prosigns() -> Translation {
+        Translation {
+            prosigns: true,
+            ..Translation::default()
+        }
+    }
+
+    #[test]
+    fn prosigns_are_sent_without_letter_gaps() {
+        let options = standard_options();
+        assert_eq!(encode_with("<AR>", &options, &prosigns()), ".-.-.");
+        assert_eq!(encode_with("K<ar>", &options, &prosigns()), "-.- .-.-.");
+        assert_ne!(
+            encode_with("<AR>", &options, &Translation::default()),
+            ".-.-."
+        );
+    }
+
+    #[test]
+    fn only_known_prosigns_run_together() {
+        let options = standard_options();
+        assert_eq!(
+            encode_with("<HH>", &options, &prosigns()),
+            encode_with("<HH>", &options, &Translation::default())
+        );
+        assert_eq!(prosign_len("<123>"), None);
+        assert_eq!(prosign_len("<SOS> 73"), Some(5));
+    }
+
+    #[test]
+    fn prosigns_decode_to_their_notation() {
+        let options = standard_options();
+        assert_eq!(decode_with("...-.-", &options, &prosigns()), "<SK>");
+        assert_eq!(decode_with("...---...", &options, &prosigns()), "<SOS>");
+        assert_eq!(decode_with(".-.-.", &options, &Translation::default()), "+");
+    }
+
+    #[test]
+    fn mixed_text_round_trips() {
+        let options = standard_options();
+        let text = "QTH BOSTON <BT> 73 K1ABC<SK>";
+        assert_eq!(
+            decode_with(
+                &encode_with(text, &options, &prosigns()),
+                &options,
+                &prosigns()
+            ),
+            text
+        );
+
+        let text = "QTH BOSTON = 73";
+        assert_eq!(decode(&encode(text, &options), &options), text);
+        assert_eq!(
+            decode_with(&encode(text, &options), &options, &prosigns()),
+            "QTH BOSTON <BT> 73"
+        );
+    }
+
+    #[test]
+    fn text_range_spans_a_whole_prosign() {
+        let text = "K<AR> E";
+        assert_eq!(text_range(text, 0, &prosigns()), Some(0..1));
+        assert_eq!(text_range(text, 1, &prosigns()), Some(1..5));
+        assert_eq!(text_range(text, 2, &prosigns()), Some(6..7));
+        assert_eq!(text_range(text, 3, &prosigns()), None);
+
+        let plain = Translation::default();
+        assert_eq!(text_range(text, 0, &plain), Some(0..1));
+        assert_ne!(text_range(text, 1, &plain), Some(1..5));
+    }
+
+    #[test]
+    fn text_range_skips_characters_that_do_not_encode() {
+        let text = "K\u{2603}M";
+        let plain = Translation::default();
+        assert_eq!(text_range(text, 0, &plain), Some(0..1));
+        assert_eq!(text_range(text, 1, &plain), Some(4..5));
+        assert_eq!(text_range(text, 2, &plain), None);
+    }
+}
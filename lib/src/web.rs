@@ -1,97 +1,592 @@
-use std::cell::RefCell;
+use std::{
+    cell::{Cell, RefCell},
+    fmt,
+    future::Future,
+    rc::Rc,
+    time::Duration,
+};
 
-use dioxus::prelude::spawn;
-use gloo::{timers::future::TimeoutFuture, utils::window};
+use gloo::{timers::callback::Interval, utils::window};
 use morsify::Options;
+use wasm_bindgen::{JsCast, JsValue, closure::Closure};
 use wasm_bindgen_futures::JsFuture;
-use web_sys::{AudioContext, OscillatorType, SpeechSynthesisUtterance};
+use web_sys::{
+    AudioBufferSourceNode, AudioContext, AudioParam, AudioScheduledSourceNode, DomException,
+    GainNode, OscillatorNode, OscillatorType, SpeechSynthesisErrorCode, SpeechSynthesisErrorEvent,
+    SpeechSynthesisUtterance, Storage,
+};
+
+use crate::{
+    Error, Result,
+    backend::Backend,
+    haptic::vibration_pattern,
+    playback::{Completion, PlaybackOutcome, ProgressEvents},
+    progress::{self, Progress},
+    timeline::{ElementKind, timeline},
+    timing::Timing,
+    tone::Tone,
+};
+
+type OnEnd = Box<dyn FnOnce(PlaybackOutcome)>;
+
+/// Number of points used to draw each envelope ramp on the gain automation.
+const ENVELOPE_POINTS: usize = 32;
+
+/// Time between scheduling a message and its first element, in seconds, so
+/// the first tone is not clipped while the audio thread picks it up.
+const START_DELAY: f64 = 0.05;
+
+/// How often progress is checked against the audio clock, in milliseconds.
+const PROGRESS_INTERVAL: u32 = 10;
+
+/// Time the gain takes to fade out when playback is stopped early, in
+/// seconds.
+const STOP_RAMP: f64 = 0.005;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    Playing,
+    Paused,
+    Stopped,
+    Finished,
+}
+
+/// A handle to Morse code or samples playing through the Web Audio API.
+///
+/// Dropping the handle does not stop playback; call [`PlaybackHandle::stop`]
+/// to silence it early.
+#[derive(Clone)]
+pub struct PlaybackHandle {
+    audio_ctx: AudioContext,
+    source: AudioScheduledSourceNode,
+    gain: GainNode,
+    state: Rc<Cell<State>>,
+    onend: Rc<RefCell<Option<OnEnd>>>,
+    progress: Rc<RefCell<Option<Interval>>>,
+}
+
+impl fmt::Debug for PlaybackHandle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PlaybackHandle")
+            .field("audio_ctx", &self.audio_ctx)
+            .field("state", &self.state)
+            .finish_non_exhaustive()
+    }
+}
+
+impl PlaybackHandle {
+    /// Stops playback, fading out any tone that is currently sounding. A
+    /// stopped playback cannot be resumed.
+    pub fn stop(&self) {
+        match self.state.replace(State::Stopped) {
+            State::Playing => {
+                // The source's `ended` event finishes the playback once the
+                // fade is over.
+                let now = self.audio_ctx.current_time();
+                let gain = self.gain.gain();
+                let _ = gain.cancel_scheduled_values(now);
+                let _ = gain.set_value_at_time(gain.value(), now);
+                let _ = gain.linear_ramp_to_value_at_time(0.0, now + STOP_RAMP);
+                let _ = self.source.stop_with_when(now + STOP_RAMP);
+            }
+            // The audio clock does not advance while suspended, so a fade
+            // would never end.
+            State::Paused => self.finish(PlaybackOutcome::Cancelled),
+            state @ (State::Stopped | State::Finished) => self.state.set(state),
+        }
+    }
+
+    /// Pauses playback, including any tone that is currently sounding.
+    pub fn pause(&self) {
+        if self.state.get() == State::Playing {
+            self.state.set(State::Paused);
+            let _ = self.audio_ctx.suspend();
+        }
+    }
+
+    /// Resumes playback after [`PlaybackHandle::pause`].
+    pub fn resume(&self) {
+        if self.state.get() == State::Paused {
+            self.state.set(State::Playing);
+            let _ = self.audio_ctx.resume();
+        }
+    }
+
+    /// Returns `true` once playback has ended, either because the message
+    /// finished or because it was stopped.
+    #[must_use]
+    pub fn is_finished(&self) -> bool {
+        matches!(self.state.get(), State::Stopped | State::Finished)
+    }
+
+    /// Closes the audio context and reports the outcome, once.
+    fn finish(&self, outcome: PlaybackOutcome) {
+        if self.state.get() != State::Stopped {
+            self.state.set(State::Finished);
+        }
+        let _ = self.audio_ctx.close();
+        self.progress.take();
+        if let Some(onend) = self.onend.borrow_mut().take() {
+            onend(outcome);
+        }
+    }
+}
 
 /// Plays a given Morse code string using the Web Audio API.
 ///
-/// # Panics
+/// The whole message is scheduled up front on the audio clock, keying a
+/// single oscillator through gain automation, so its timing does not depend
+/// on the page's timers. The returned [`PlaybackHandle`] can stop, pause or
+/// resume playback. `onend` is called when the oscillator stops, after the
+/// last element has sounded or playback was stopped.
 ///
-/// This function will panic if:
-/// - The `AudioContext` or `OscillatorNode` cannot be created.
-/// - The `OscillatorNode` fails to connect to the audio destination.
-/// - The `OscillatorNode` fails to start or stop.
+/// # Errors
+///
+/// Returns [`Error::Audio`] if the browser cannot create an `AudioContext` or
+/// schedule the message.
 pub fn play_morse(
     morse_code: &str,
     options: Options,
-    frequency: f32,
-    time_unit: u32,
-    mut onend: impl FnMut() + 'static,
-) {
-    use std::rc::Rc;
-
-    let audio_ctx = Rc::new(AudioContext::new().unwrap());
-    let o = Rc::new(RefCell::new({
-        let o = audio_ctx.create_oscillator().unwrap();
-        o.set_type(web_sys::OscillatorType::Sine);
-        o.connect_with_audio_node(&audio_ctx.destination()).unwrap();
-        o
-    }));
-    let morse_code_cloned = morse_code.to_owned();
-
-    spawn(async move {
-        let play_tone = || {
-            *o.borrow_mut() = audio_ctx.create_oscillator().unwrap();
-            o.borrow().set_type(OscillatorType::Sine);
-            o.borrow().frequency().set_value(frequency);
-            o.borrow()
-                .connect_with_audio_node(&audio_ctx.destination())
-                .unwrap();
-            o.borrow().start().unwrap();
-        };
+    tone: Tone,
+    timing: Timing,
+    onend: impl FnOnce(PlaybackOutcome) + 'static,
+) -> Result<PlaybackHandle> {
+    play_morse_with_progress(morse_code, options, tone, timing, |_| {}, onend)
+}
 
-        for char in morse_code_cloned.chars() {
-            match char {
-                c if c == options.dot => {
-                    play_tone();
-                    TimeoutFuture::new(time_unit).await;
-                    o.borrow().stop().unwrap();
-                }
-                c if c == options.dash => {
-                    play_tone();
-                    TimeoutFuture::new(time_unit * 3).await;
-                    o.borrow().stop().unwrap();
-                }
-
-                c if c == options.separator => {
-                    TimeoutFuture::new(time_unit * 3).await;
-                }
-
-                c if c == options.space => {
-                    TimeoutFuture::new(time_unit * 3).await;
-                }
-
-                _ => {
-                    TimeoutFuture::new(time_unit * 7).await;
-                }
+/// Plays a given Morse code string like [`play_morse`], also calling
+/// `onprogress` as each element starts sounding.
+///
+/// Progress is read from the audio clock, so it pauses along with playback,
+/// but it is delivered by a page timer that may lag while the tab is in the
+/// background.
+///
+/// # Errors
+///
+/// Returns [`Error::Audio`] if the browser cannot create an `AudioContext` or
+/// schedule the message.
+pub fn play_morse_with_progress(
+    morse_code: &str,
+    options: Options,
+    tone: Tone,
+    timing: Timing,
+    mut onprogress: impl FnMut(Progress) + 'static,
+    onend: impl FnOnce(PlaybackOutcome) + 'static,
+) -> Result<PlaybackHandle> {
+    let audio_ctx = AudioContext::new().map_err(|err| Error::Audio(js_error(&err)))?;
+    let scheduled = schedule(&audio_ctx, morse_code, &options, tone, &timing);
+    let (oscillator, gain, start) = match scheduled {
+        Ok(nodes) => nodes,
+        Err(err) => {
+            let _ = audio_ctx.close();
+            return Err(Error::Audio(js_error(&err)));
+        }
+    };
+
+    let handle = PlaybackHandle {
+        audio_ctx,
+        source: oscillator.into(),
+        gain,
+        state: Rc::new(Cell::new(State::Playing)),
+        onend: Rc::new(RefCell::new(Some(Box::new(onend)))),
+        progress: Rc::default(),
+    };
+
+    let mut events = progress::schedule(morse_code, &options, &timing)
+        .into_iter()
+        .peekable();
+    let progress = Interval::new(PROGRESS_INTERVAL, {
+        let audio_ctx = handle.audio_ctx.clone();
+        move || {
+            let position = audio_ctx.current_time() - start;
+            while let Some(progress) =
+                events.next_if(|progress| progress.start.as_secs_f64() <= position)
+            {
+                onprogress(progress);
             }
+        }
+    });
+    handle.progress.replace(Some(progress));
+    finish_on_end(&handle);
 
-            TimeoutFuture::new(time_unit).await;
+    Ok(handle)
+}
+
+/// Plays mono samples in `-1.0..=1.0` at `sample_rate`, such as a pile-up
+/// rendered by [`mix`](crate::render::mix), through an audio buffer. The
+/// returned [`PlaybackHandle`] works as it does for [`play_morse`], and
+/// `onend` is called once the buffer has played or playback was stopped.
+///
+/// # Errors
+///
+/// Returns [`Error::Audio`] if the browser cannot create an `AudioContext` or
+/// a buffer at `sample_rate`.
+pub fn play_samples(
+    samples: &[f32],
+    sample_rate: u32,
+    onend: impl FnOnce(PlaybackOutcome) + 'static,
+) -> Result<PlaybackHandle> {
+    let audio_ctx = AudioContext::new().map_err(|err| Error::Audio(js_error(&err)))?;
+    let (source, gain) = match buffer_source(&audio_ctx, samples, sample_rate) {
+        Ok(nodes) => nodes,
+        Err(err) => {
+            let _ = audio_ctx.close();
+            return Err(Error::Audio(js_error(&err)));
         }
+    };
+
+    let handle = PlaybackHandle {
+        audio_ctx,
+        source: source.into(),
+        gain,
+        state: Rc::new(Cell::new(State::Playing)),
+        onend: Rc::new(RefCell::new(Some(Box::new(onend)))),
+        progress: Rc::default(),
+    };
+    finish_on_end(&handle);
+
+    Ok(handle)
+}
 
-        onend();
+/// Finishes `handle` with the outcome of its playback when its source ends.
+fn finish_on_end(handle: &PlaybackHandle) {
+    let on_source_end = Closure::once_into_js({
+        let handle = handle.clone();
+        move || {
+            let outcome = match handle.state.get() {
+                State::Stopped => PlaybackOutcome::Cancelled,
+                _ => PlaybackOutcome::Completed,
+            };
+            handle.finish(outcome);
+        }
     });
+    handle
+        .source
+        .set_onended(Some(on_source_end.unchecked_ref()));
 }
 
-pub fn play_text(text: &str, mut onend: impl FnMut() + 'static) {
-    let synth = window().speech_synthesis().unwrap();
-    let utterance = SpeechSynthesisUtterance::new().unwrap();
-    utterance.set_text(text);
-    synth.speak(&utterance);
+/// Creates a buffer source holding `samples`, connected through a gain node
+/// so it can be faded out, and starts it.
+fn buffer_source(
+    audio_ctx: &AudioContext,
+    samples: &[f32],
+    sample_rate: u32,
+) -> Result<(AudioBufferSourceNode, GainNode), JsValue> {
+    // Buffers cannot be empty.
+    let length = u32::try_from(samples.len()).unwrap_or(u32::MAX).max(1);
+    let buffer = audio_ctx.create_buffer(1, length, sample_rate as f32)?;
+    buffer.copy_to_channel(samples, 0)?;
+
+    let source = audio_ctx.create_buffer_source()?;
+    source.set_buffer(Some(&buffer));
+    let gain = audio_ctx.create_gain()?;
+    source.connect_with_audio_node(&gain)?;
+    gain.connect_with_audio_node(&audio_ctx.destination())?;
+    source.start_with_when(audio_ctx.current_time() + START_DELAY)?;
+    Ok((source, gain))
+}
+
+/// Creates an oscillator keyed by a gain node, and schedules every tone of
+/// the message on them. Returns the nodes and the audio clock time the
+/// message starts at.
+fn schedule(
+    audio_ctx: &AudioContext,
+    morse_code: &str,
+    options: &Options,
+    tone: Tone,
+    timing: &Timing,
+) -> Result<(OscillatorNode, GainNode, f64), JsValue> {
+    let oscillator = audio_ctx.create_oscillator()?;
+    oscillator.set_type(OscillatorType::Sine);
+    oscillator.frequency().set_value(tone.frequency);
+    let gain = audio_ctx.create_gain()?;
+    gain.gain().set_value(0.0);
+    oscillator.connect_with_audio_node(&gain)?;
+    gain.connect_with_audio_node(&audio_ctx.destination())?;
+
+    let start = audio_ctx.current_time() + START_DELAY;
+    let mut offset = Duration::ZERO;
+    for element in timeline(morse_code, options, timing) {
+        if element.kind == ElementKind::On {
+            key(
+                &gain.gain(),
+                tone,
+                start + offset.as_secs_f64(),
+                element.duration,
+            )?;
+        }
+        offset += element.duration;
+    }
+
+    oscillator.start_with_when(start)?;
+    oscillator.stop_with_when(start + offset.as_secs_f64())?;
+    Ok((oscillator, gain, start))
+}
+
+/// Schedules one tone of `duration` at `time` on the audio clock, shaped by
+/// the tone's envelope.
+fn key(gain: &AudioParam, tone: Tone, time: f64, duration: Duration) -> Result<(), JsValue> {
+    let length = duration.as_secs_f64();
+    let rise = tone.rise_time_for(duration).as_secs_f64();
+
+    if rise > 0.0 {
+        let mut attack = tone.envelope.curve(ENVELOPE_POINTS);
+        let mut release: Vec<f32> = attack.iter().rev().copied().collect();
+        gain.set_value_curve_at_time(&mut attack, time, rise)?;
+        gain.set_value_curve_at_time(&mut release, time + length - rise, rise)?;
+    } else {
+        gain.set_value_at_time(1.0, time)?;
+        gain.set_value_at_time(0.0, time + length)?;
+    }
+    Ok(())
+}
 
-    onend();
+/// Vibrates a given Morse code string through `navigator.vibrate`, keying
+/// the motor the way [`play_morse`] keys the tone.
+///
+/// The browser runs the pattern on its own once started;
+/// [`stop_vibration`] cancels it.
+///
+/// # Errors
+///
+/// Returns [`Error::Haptic`] if the browser cannot vibrate or refuses the
+/// pattern, for example because the page has not been interacted with yet.
+pub fn vibrate_morse(morse_code: &str, options: &Options, timing: &Timing) -> Result<()> {
+    let navigator = window().navigator();
+    if !js_sys::Reflect::has(&navigator, &JsValue::from_str("vibrate")).unwrap_or(false) {
+        return Err(Error::Haptic(
+            "this browser does not support vibration".to_string(),
+        ));
+    }
+
+    let pattern = vibration_pattern(morse_code, options, timing);
+    if navigator.vibrate_with_pattern(&JsValue::from(pattern)) {
+        Ok(())
+    } else {
+        Err(Error::Haptic(
+            "the browser refused the vibration pattern".to_string(),
+        ))
+    }
 }
 
-pub fn copy_to_clipboard(text: &str) {
+/// Cancels the vibration started by [`vibrate_morse`].
+pub fn stop_vibration() {
     let navigator = window().navigator();
-    let clipboard = navigator.clipboard();
+    if js_sys::Reflect::has(&navigator, &JsValue::from_str("vibrate")).unwrap_or(false) {
+        navigator.vibrate_with_duration(0);
+    }
+}
 
-    let text = text.to_string();
+/// Reads the given text aloud using the Web Speech API.
+///
+/// `onend` is called once the utterance has finished, was cancelled, or
+/// failed to play.
+///
+/// # Errors
+///
+/// Returns [`Error::Speech`] if the browser does not support speech
+/// synthesis. `onend` is not called in that case.
+pub fn play_text(text: &str, onend: impl FnOnce(PlaybackOutcome) + 'static) -> Result<()> {
+    let synth = window()
+        .speech_synthesis()
+        .map_err(|err| Error::Speech(js_error(&err)))?;
+    let utterance = SpeechSynthesisUtterance::new().map_err(|err| Error::Speech(js_error(&err)))?;
+    utterance.set_text(text);
 
-    spawn(async move {
-        JsFuture::from(clipboard.write_text(&text)).await.unwrap();
+    // Browsers may fire both `error` and `end` for one utterance, so only the
+    // first event reports the outcome.
+    let onend: Rc<RefCell<Option<OnEnd>>> = Rc::new(RefCell::new(Some(Box::new(onend))));
+
+    let on_utterance_end = Closure::once_into_js({
+        let onend = Rc::clone(&onend);
+        move || {
+            if let Some(onend) = onend.borrow_mut().take() {
+                onend(PlaybackOutcome::Completed);
+            }
+        }
+    });
+    let on_utterance_error = Closure::once_into_js(move |event: SpeechSynthesisErrorEvent| {
+        let outcome = match event.error() {
+            SpeechSynthesisErrorCode::Canceled | SpeechSynthesisErrorCode::Interrupted => {
+                PlaybackOutcome::Cancelled
+            }
+            error => PlaybackOutcome::Failed(Error::Speech(format!("{error:?}"))),
+        };
+        if let Some(onend) = onend.borrow_mut().take() {
+            onend(outcome);
+        }
     });
+    utterance.set_onend(Some(on_utterance_end.unchecked_ref()));
+    utterance.set_onerror(Some(on_utterance_error.unchecked_ref()));
+
+    synth.speak(&utterance);
+    Ok(())
+}
+
+/// Cancels the utterances started by [`play_text`]. Their `onend` is called
+/// with [`PlaybackOutcome::Cancelled`].
+pub fn stop_text() {
+    if let Ok(synth) = window().speech_synthesis() {
+        synth.cancel();
+    }
+}
+
+/// Copies the given text to the clipboard.
+///
+/// # Errors
+///
+/// Returns [`Error::Clipboard`] if the browser denies clipboard access, for
+/// example because the page is not focused.
+pub async fn copy_to_clipboard(text: &str) -> Result<()> {
+    let clipboard = window().navigator().clipboard();
+
+    JsFuture::from(clipboard.write_text(text))
+        .await
+        .map(drop)
+        .map_err(|err| Error::Clipboard(js_error(&err)))
+}
+
+/// Reads text from the clipboard.
+///
+/// # Errors
+///
+/// Returns [`Error::Clipboard`] if the browser denies clipboard access or
+/// the clipboard holds no text.
+pub async fn read_from_clipboard() -> Result<String> {
+    let clipboard = window().navigator().clipboard();
+
+    let text = JsFuture::from(clipboard.read_text())
+        .await
+        .map_err(|err| Error::Clipboard(js_error(&err)))?;
+    text.as_string()
+        .ok_or_else(|| Error::Clipboard("the clipboard holds no text".to_string()))
+}
+
+/// Reads the setting saved under `key` in the browser's local storage.
+///
+/// # Errors
+///
+/// Returns [`Error::Storage`] if local storage is unavailable, for example
+/// because the user has disabled it.
+pub fn read_setting(key: &str) -> Result<Option<String>> {
+    local_storage()?
+        .get_item(key)
+        .map_err(|err| Error::Storage(js_error(&err)))
+}
+
+/// Saves `value` under `key` in the browser's local storage.
+///
+/// # Errors
+///
+/// Returns [`Error::Storage`] if local storage is unavailable or full.
+pub fn write_setting(key: &str, value: &str) -> Result<()> {
+    local_storage()?
+        .set_item(key, value)
+        .map_err(|err| Error::Storage(js_error(&err)))
+}
+
+fn local_storage() -> Result<Storage> {
+    window()
+        .local_storage()
+        .map_err(|err| Error::Storage(js_error(&err)))?
+        .ok_or_else(|| Error::Storage("local storage is unavailable".to_string()))
+}
+
+/// The [`Backend`] for browsers, built on the Web Audio, Web Speech,
+/// Clipboard and Web Storage APIs. Clones share the playback they can stop.
+#[derive(Debug, Clone, Default)]
+pub struct Platform {
+    playback: Rc<RefCell<Option<PlaybackHandle>>>,
+}
+
+impl Backend for Platform {
+    fn play_morse(
+        &self,
+        morse_code: &str,
+        options: Options,
+        tone: Tone,
+        timing: Timing,
+    ) -> Result<Completion> {
+        self.play_morse_with_progress(morse_code, options, tone, timing)
+            .map(|(completion, _)| completion)
+    }
+
+    fn play_morse_with_progress(
+        &self,
+        morse_code: &str,
+        options: Options,
+        tone: Tone,
+        timing: Timing,
+    ) -> Result<(Completion, ProgressEvents)> {
+        let (completer, completion) = Completion::new();
+        let (sender, events) = ProgressEvents::new();
+        let handle = play_morse_with_progress(
+            morse_code,
+            options,
+            tone,
+            timing,
+            move |progress| sender.send(progress),
+            |outcome| completer.complete(outcome),
+        )?;
+        if let Some(previous) = self.playback.replace(Some(handle)) {
+            previous.stop();
+        }
+        Ok((completion, events))
+    }
+
+    fn play_samples(&self, samples: Vec<f32>, sample_rate: u32) -> Result<Completion> {
+        let (completer, completion) = Completion::new();
+        let handle = play_samples(&samples, sample_rate, |outcome| {
+            completer.complete(outcome);
+        })?;
+        if let Some(previous) = self.playback.replace(Some(handle)) {
+            previous.stop();
+        }
+        Ok(completion)
+    }
+
+    fn vibrate_morse(&self, morse_code: &str, options: &Options, timing: &Timing) -> Result<()> {
+        vibrate_morse(morse_code, options, timing)
+    }
+
+    fn play_text(&self, text: &str) -> Result<Completion> {
+        // The browser queues utterances rather than replacing them.
+        stop_text();
+        let (completer, completion) = Completion::new();
+        play_text(text, |outcome| completer.complete(outcome))?;
+        Ok(completion)
+    }
+
+    fn stop(&self) {
+        if let Some(handle) = self.playback.take() {
+            handle.stop();
+        }
+        stop_vibration();
+        stop_text();
+    }
+
+    fn copy_to_clipboard(&self, text: &str) -> impl Future<Output = Result<()>> {
+        let text = text.to_string();
+        async move { copy_to_clipboard(&text).await }
+    }
+
+    fn read_clipboard(&self) -> impl Future<Output = Result<String>> {
+        read_from_clipboard()
+    }
+
+    fn read_setting(&self, key: &str) -> Result<Option<String>> {
+        read_setting(key)
+    }
+
+    fn write_setting(&self, key: &str, value: &str) -> Result<()> {
+        write_setting(key, value)
+    }
+}
+
+/// The message of an exception thrown by a browser API.
+fn js_error(err: &JsValue) -> String {
+    err.dyn_ref::<DomException>()
+        .map(DomException::message)
+        .or_else(|| err.as_string())
+        .unwrap_or_else(|| format!("{err:?}"))
 }
@@ -1,22 +1,11 @@
 use gtk4::{glib, prelude::*};
 
-fn main() -> glib::ExitCode {
-    let application = gtk4::Application::builder()
-        .application_id("com.github.gtk-rs.examples.basic")
-        .build();
-    application.connect_activate(build_ui);
-    application.run()
-}
-
-fn build_ui(application: &gtk4::Application) {
-    let window = gtk4::ApplicationWindow::new(application);
+mod window;
 
-    window.set_title(Some("First GTK Program"));
-    window.set_default_size(350, 70);
+const APP_ID: &str = "io.github.dot_dash.DotDash";
 
-    let button = gtk4::Button::with_label("Click me!");
-
-    window.set_child(Some(&button));
-
-    window.present();
+fn main() -> glib::ExitCode {
+    let application = gtk4::Application::builder().application_id(APP_ID).build();
+    application.connect_activate(window::build_ui);
+    application.run()
 }
@@ -0,0 +1,468 @@
+use std::{cell::Cell, ops::Range, rc::Rc};
+
+use dot_dash::{
+    Error, Result,
+    alphabet::Alphabet,
+    backend::{Backend, Platform},
+    playback::{Completion, PlaybackOutcome},
+    progress::Progress,
+    timeline::ElementKind,
+    timing::Timing,
+    tone::Tone,
+    translate::{Translation, decode_with, encode_with, text_range},
+};
+use gtk4::{
+    Align, Application, ApplicationWindow, Box as GtkBox, Button, CheckButton, Entry, Expander,
+    Grid, Image, Label, Orientation, ScrolledWindow, SpinButton, TextBuffer, TextView, Widget,
+    WrapMode, glib, prelude::*,
+};
+use morsify::Options;
+
+/// Name of the text tag marking the character being played.
+const HIGHLIGHT_TAG: &str = "highlight";
+
+/// Opacity of the keying lamp while the tone is off.
+const LAMP_OFF: f64 = 0.2;
+
+/// The play and stop buttons of one text view; only one is shown at a time.
+struct PlayControls {
+    play: Button,
+    stop: Button,
+}
+
+impl PlayControls {
+    fn new() -> Self {
+        let stop = icon_button("media-playback-stop-symbolic", "Stop the Audio");
+        stop.set_visible(false);
+
+        Self {
+            play: icon_button("media-playback-start-symbolic", "Play the Audio"),
+            stop,
+        }
+    }
+
+    fn set_playing(&self, playing: bool) {
+        self.play.set_visible(!playing);
+        self.stop.set_visible(playing);
+    }
+}
+
+/// The widgets and playback state of the translator window.
+struct Translator {
+    text: TextBuffer,
+    morse: TextBuffer,
+    dot: Entry,
+    dash: Entry,
+    space: Entry,
+    separator: Entry,
+    prosigns: CheckButton,
+    wpm: SpinButton,
+    farnsworth_wpm: SpinButton,
+    frequency: SpinButton,
+    text_controls: PlayControls,
+    morse_controls: PlayControls,
+    error: Label,
+    /// Lights up while the tone is keyed.
+    lamp: Image,
+    platform: Platform,
+    /// Set while one buffer is rewritten from the other, so the change does
+    /// not translate back.
+    syncing: Cell<bool>,
+}
+
+impl Translator {
+    fn options(&self) -> Options {
+        let symbol = |entry: &Entry, default| entry.text().chars().next().unwrap_or(default);
+
+        Options {
+            dot: symbol(&self.dot, '.'),
+            dash: symbol(&self.dash, '-'),
+            space: symbol(&self.space, '/'),
+            separator: symbol(&self.separator, ' '),
+            ..Default::default()
+        }
+    }
+
+    fn translation(&self) -> Translation {
+        Translation {
+            prosigns: self.prosigns.is_active(),
+            alphabet: Alphabet::detect(&buffer_text(&self.text)).unwrap_or_default(),
+            ..Translation::default()
+        }
+    }
+
+    fn timing(&self) -> Timing {
+        Timing::farnsworth(
+            self.wpm.value_as_int().unsigned_abs(),
+            self.farnsworth_wpm.value_as_int().unsigned_abs(),
+        )
+    }
+
+    fn encode_text(&self) {
+        if !self.syncing.replace(true) {
+            self.morse.set_text(&encode_with(
+                &buffer_text(&self.text),
+                &self.options(),
+                &self.translation(),
+            ));
+            self.syncing.set(false);
+        }
+    }
+
+    fn decode_morse(&self) {
+        if !self.syncing.replace(true) {
+            self.text.set_text(&decode_with(
+                &buffer_text(&self.morse),
+                &self.options(),
+                &self.translation(),
+            ));
+            self.syncing.set(false);
+        }
+    }
+
+    fn show_error(&self, err: &Error) {
+        self.error.set_text(&err.to_string());
+        self.error.set_visible(true);
+    }
+
+    /// Lights the lamp and highlights the character being played, or clears
+    /// both once playback has ended.
+    fn show_progress(&self, progress: Option<&Progress>) {
+        let keyed = progress.is_some_and(|progress| progress.kind == ElementKind::On);
+        self.lamp.set_opacity(if keyed { 1.0 } else { LAMP_OFF });
+
+        highlight(&self.morse, progress.map(|progress| progress.morse.clone()));
+        highlight(
+            &self.text,
+            progress.and_then(|progress| {
+                text_range(
+                    &buffer_text(&self.text),
+                    progress.character,
+                    &self.translation(),
+                )
+            }),
+        );
+    }
+
+    /// Shows the stop button of `controls` until the playback has ended.
+    fn await_outcome(
+        self: &Rc<Self>,
+        played: Result<Completion>,
+        controls: fn(&Self) -> &PlayControls,
+    ) {
+        let completion = match played {
+            Ok(completion) => completion,
+            Err(err) => {
+                self.show_error(&err);
+                return;
+            }
+        };
+        controls(self).set_playing(true);
+
+        let this = Rc::clone(self);
+        glib::spawn_future_local(async move {
+            let outcome = completion.await;
+            controls(&this).set_playing(false);
+            if let PlaybackOutcome::Failed(err) = outcome {
+                this.show_error(&err);
+            }
+        });
+    }
+
+    fn play_text(self: &Rc<Self>) {
+        self.error.set_visible(false);
+
+        let played = self.platform.play_text(&buffer_text(&self.text));
+        self.await_outcome(played, |this| &this.text_controls);
+    }
+
+    fn play_morse(self: &Rc<Self>) {
+        self.error.set_visible(false);
+
+        let played = self
+            .platform
+            .play_morse_with_progress(
+                &buffer_text(&self.morse),
+                self.options(),
+                Tone::new(self.frequency.value() as f32),
+                self.timing(),
+            )
+            .map(|(completion, mut events)| {
+                let this = Rc::clone(self);
+                glib::spawn_future_local(async move {
+                    while let Some(progress) = events.next().await {
+                        this.show_progress(Some(&progress));
+                    }
+                    this.show_progress(None);
+                });
+                completion
+            });
+        self.await_outcome(played, |this| &this.morse_controls);
+    }
+}
+
+fn buffer_text(buffer: &TextBuffer) -> String {
+    buffer
+        .text(&buffer.start_iter(), &buffer.end_iter(), false)
+        .to_string()
+}
+
+/// Marks the given byte range of the buffer's text, removing any earlier
+/// mark.
+fn highlight(buffer: &TextBuffer, range: Option<Range<usize>>) {
+    buffer.remove_tag_by_name(HIGHLIGHT_TAG, &buffer.start_iter(), &buffer.end_iter());
+
+    let text = buffer_text(buffer);
+    let offset = |index: usize| {
+        text.get(..index)
+            .map(|before| i32::try_from(before.chars().count()).unwrap_or(i32::MAX))
+    };
+    if let Some(range) = range
+        && let (Some(start), Some(end)) = (offset(range.start), offset(range.end))
+    {
+        buffer.apply_tag_by_name(
+            HIGHLIGHT_TAG,
+            &buffer.iter_at_offset(start),
+            &buffer.iter_at_offset(end),
+        );
+    }
+}
+
+fn icon_button(icon_name: &str, tooltip: &str) -> Button {
+    let button = Button::from_icon_name(icon_name);
+    button.set_tooltip_text(Some(tooltip));
+    button.add_css_class("flat");
+    button
+}
+
+fn copy_button(translator: &Rc<Translator>, buffer: &TextBuffer) -> Button {
+    let button = icon_button("edit-copy-symbolic", "Click to Copy");
+    let translator = Rc::clone(translator);
+    let buffer = buffer.clone();
+    button.connect_clicked(move |_| {
+        let translator = Rc::clone(&translator);
+        let text = buffer_text(&buffer);
+        glib::spawn_future_local(async move {
+            if let Err(err) = translator.platform.copy_to_clipboard(&text).await {
+                translator.show_error(&err);
+            }
+        });
+    });
+    button
+}
+
+fn paste_button(translator: &Rc<Translator>, buffer: &TextBuffer) -> Button {
+    let button = icon_button("edit-paste-symbolic", "Paste from the Clipboard");
+    let translator = Rc::clone(translator);
+    let buffer = buffer.clone();
+    button.connect_clicked(move |_| {
+        let translator = Rc::clone(&translator);
+        let buffer = buffer.clone();
+        glib::spawn_future_local(async move {
+            match translator.platform.read_clipboard().await {
+                Ok(text) => buffer.set_text(text.trim()),
+                Err(err) => translator.show_error(&err),
+            }
+        });
+    });
+    button
+}
+
+/// A titled text view with its action buttons in the header.
+fn text_column(title: &str, buffer: &TextBuffer, buttons: &[&Widget]) -> GtkBox {
+    let header = GtkBox::new(Orientation::Horizontal, 4);
+    let label = Label::new(Some(title));
+    label.add_css_class("heading");
+    label.set_hexpand(true);
+    label.set_halign(Align::Start);
+    header.append(&label);
+    for button in buttons {
+        header.append(*button);
+    }
+
+    let view = TextView::with_buffer(buffer);
+    view.set_wrap_mode(WrapMode::WordChar);
+    view.set_monospace(true);
+    let scrolled = ScrolledWindow::builder()
+        .child(&view)
+        .hexpand(true)
+        .vexpand(true)
+        .build();
+    scrolled.add_css_class("frame");
+
+    let column = GtkBox::new(Orientation::Vertical, 6);
+    column.append(&header);
+    column.append(&scrolled);
+    column
+}
+
+/// A text buffer with the tag [`highlight`] marks characters with.
+fn highlight_buffer() -> TextBuffer {
+    let buffer = TextBuffer::new(None);
+    buffer.create_tag(
+        Some(HIGHLIGHT_TAG),
+        &[("background", &"#f6d32d"), ("foreground", &"#000000")],
+    );
+    buffer
+}
+
+fn symbol_entry(symbol: char) -> Entry {
+    let entry = Entry::builder()
+        .text(symbol.to_string())
+        .max_length(1)
+        .width_chars(3)
+        .build();
+    entry.set_hexpand(true);
+    entry
+}
+
+fn spin_button(value: f64, min: f64, max: f64, step: f64) -> SpinButton {
+    let spin = SpinButton::with_range(min, max, step);
+    spin.set_value(value);
+    spin.set_hexpand(true);
+    spin
+}
+
+fn options_panel(translator: &Translator) -> Expander {
+    let grid = Grid::builder()
+        .row_spacing(6)
+        .column_spacing(12)
+        .margin_top(6)
+        .build();
+
+    let fields: [(&str, &gtk4::Widget); 8] = [
+        ("Dot", translator.dot.upcast_ref()),
+        ("Dash", translator.dash.upcast_ref()),
+        ("Space", translator.space.upcast_ref()),
+        ("Separator", translator.separator.upcast_ref()),
+        ("Prosigns", translator.prosigns.upcast_ref()),
+        ("WPM", translator.wpm.upcast_ref()),
+        ("Farnsworth WPM", translator.farnsworth_wpm.upcast_ref()),
+        ("Frequency (Hz)", translator.frequency.upcast_ref()),
+    ];
+    for (i, (title, widget)) in (0..).zip(fields) {
+        let label = Label::new(Some(title));
+        label.set_halign(Align::Start);
+        grid.attach(&label, (i % 4) * 2, i / 4, 1, 1);
+        grid.attach(widget, (i % 4) * 2 + 1, i / 4, 1, 1);
+    }
+
+    Expander::builder().label("Options").child(&grid).build()
+}
+
+pub fn build_ui(application: &Application) {
+    let translator = Rc::new(Translator {
+        text: highlight_buffer(),
+        morse: highlight_buffer(),
+        dot: symbol_entry('.'),
+        dash: symbol_entry('-'),
+        space: symbol_entry('/'),
+        separator: symbol_entry(' '),
+        prosigns: CheckButton::new(),
+        wpm: spin_button(20.0, 1.0, 60.0, 1.0),
+        farnsworth_wpm: spin_button(20.0, 1.0, 60.0, 1.0),
+        frequency: spin_button(700.0, 200.0, 2000.0, 10.0),
+        text_controls: PlayControls::new(),
+        morse_controls: PlayControls::new(),
+        error: Label::new(None),
+        lamp: Image::from_icon_name("media-record-symbolic"),
+        platform: Platform::default(),
+        syncing: Cell::new(false),
+    });
+    translator.error.add_css_class("error");
+    translator.error.set_wrap(true);
+    translator.error.set_visible(false);
+    translator.lamp.set_tooltip_text(Some("Keying"));
+    translator.lamp.set_opacity(LAMP_OFF);
+
+    translator.text.connect_changed({
+        let translator = Rc::clone(&translator);
+        move |_| translator.encode_text()
+    });
+    translator.morse.connect_changed({
+        let translator = Rc::clone(&translator);
+        move |_| translator.decode_morse()
+    });
+    for entry in [
+        &translator.dot,
+        &translator.dash,
+        &translator.space,
+        &translator.separator,
+    ] {
+        entry.connect_changed({
+            let translator = Rc::clone(&translator);
+            move |_| translator.encode_text()
+        });
+    }
+    translator
+        .prosigns
+        .set_tooltip_text(Some("Send prosigns written as <AR> as one character"));
+    translator.prosigns.connect_toggled({
+        let translator = Rc::clone(&translator);
+        move |_| translator.encode_text()
+    });
+    translator.text_controls.play.connect_clicked({
+        let translator = Rc::clone(&translator);
+        move |_| translator.play_text()
+    });
+    translator.text_controls.stop.connect_clicked({
+        let translator = Rc::clone(&translator);
+        move |_| translator.platform.stop()
+    });
+    translator.morse_controls.play.connect_clicked({
+        let translator = Rc::clone(&translator);
+        move |_| translator.play_morse()
+    });
+    translator.morse_controls.stop.connect_clicked({
+        let translator = Rc::clone(&translator);
+        move |_| translator.platform.stop()
+    });
+
+    let columns = GtkBox::builder()
+        .orientation(Orientation::Horizontal)
+        .spacing(12)
+        .homogeneous(true)
+        .build();
+    columns.append(&text_column(
+        "Text",
+        &translator.text,
+        &[
+            translator.text_controls.play.upcast_ref(),
+            translator.text_controls.stop.upcast_ref(),
+            copy_button(&translator, &translator.text).upcast_ref(),
+        ],
+    ));
+    columns.append(&text_column(
+        "Morse Code",
+        &translator.morse,
+        &[
+            translator.lamp.upcast_ref(),
+            translator.morse_controls.play.upcast_ref(),
+            translator.morse_controls.stop.upcast_ref(),
+            copy_button(&translator, &translator.morse).upcast_ref(),
+            paste_button(&translator, &translator.morse).upcast_ref(),
+        ],
+    ));
+
+    let content = GtkBox::builder()
+        .orientation(Orientation::Vertical)
+        .spacing(12)
+        .margin_top(12)
+        .margin_bottom(12)
+        .margin_start(12)
+        .margin_end(12)
+        .build();
+    content.append(&translator.error);
+    content.append(&columns);
+    content.append(&options_panel(&translator));
+
+    let window = ApplicationWindow::builder()
+        .application(application)
+        .title("Dot Dash")
+        .default_width(900)
+        .default_height(600)
+        .child(&content)
+        .build();
+
+    window.present();
+}
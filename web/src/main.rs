@@ -1,5 +1,5 @@
 use dioxus::prelude::*;
-use routes::home::Home;
+use routes::{contest::Contest, home::Home, practice::Practice, training::Training};
 
 pub mod routes;
 
@@ -9,6 +9,12 @@ const STYLE: Asset = asset!("./public/styles/tailwind/tailwind.css");
 enum Route {
     #[route("/")]
     Home {},
+    #[route("/training")]
+    Training {},
+    #[route("/practice")]
+    Practice {},
+    #[route("/contest")]
+    Contest {},
 }
 
 fn main() {
@@ -0,0 +1,314 @@
+use dioxus::prelude::*;
+use dot_dash::{
+    backend::{Backend, Platform},
+    contest::{pile_up_samples, Caller, Contest as Generator, Exchange, PileUp},
+    translate::encode,
+};
+use morsify::Options as MorseOptions;
+
+use super::{home::await_playback, random_seed, ErrorAlert};
+use crate::Route;
+
+/// The sample rate pile-ups are mixed at.
+const SAMPLE_RATE: u32 = 44100;
+
+/// The stations heard in one round, and what they sent.
+#[derive(Debug, Clone, PartialEq)]
+struct Round {
+    callers: Vec<Caller>,
+    /// A lone caller's callsign and exchange, or the callsigns of a pile-up.
+    sent: String,
+}
+
+/// Plays `round`: a lone caller through the Morse code player, and a
+/// pile-up as one mixed buffer.
+fn play_round(
+    platform: &Platform,
+    round: &Round,
+    is_playing: Signal<bool>,
+    error: Signal<Option<String>>,
+) {
+    let options = MorseOptions::default();
+    let played = match round.callers.as_slice() {
+        [caller] => platform.play_morse(
+            &encode(&round.sent, &options),
+            options,
+            caller.tone,
+            caller.timing,
+        ),
+        callers => {
+            platform.play_samples(pile_up_samples(callers, &options, SAMPLE_RATE), SAMPLE_RATE)
+        }
+    };
+    await_playback(played, is_playing, error);
+}
+
+#[component]
+pub fn Contest() -> Element {
+    let mut generator = use_signal(|| Generator::new(Exchange::default(), random_seed()));
+    let mut pile_up = use_signal(|| PileUp {
+        callers: 1,
+        ..PileUp::default()
+    });
+    let mut round = use_signal(|| None::<Round>);
+    let mut copy = use_signal(String::new);
+    let mut checked = use_signal(|| false);
+    let mut is_playing = use_signal(|| false);
+    let error = use_signal(|| None::<String>);
+    let platform = use_context_provider(Platform::default);
+
+    rsx! {
+        div { class: "w-full mx-auto h-4/5 p-5",
+            ErrorAlert { error }
+            div { class: "flex justify-between items-center mb-6",
+                h1 { class: "text-lg sm:text-2xl font-bold dark:text-white", "Contest Practice" }
+                Link { class: "link link-info", to: Route::Home {}, "Translator" }
+            }
+            div { class: "grid grid-cols-2 md:grid-cols-6 gap-6 mb-6",
+                div { class: "form-control w-full",
+                    label { r#for: "exchange", class: "label",
+                        span { class: "label-text font-semibold text-base dark:text-white",
+                            "Exchange"
+                        }
+                    }
+                    select {
+                        class: "select select-bordered w-full dark:text-white dark:bg-[#24283B]",
+                        id: "exchange",
+                        onchange: move |e| {
+                            let index = e.value().parse::<usize>().unwrap_or_default();
+                            generator.write().exchange = Exchange::ALL[index.min(Exchange::ALL.len() - 1)];
+                        },
+                        for (index, exchange) in Exchange::ALL.into_iter().enumerate() {
+                            option {
+                                value: index.to_string(),
+                                selected: generator.read().exchange == exchange,
+                                {exchange.name()}
+                            }
+                        }
+                    }
+                }
+                div { class: "form-control w-full",
+                    label { r#for: "callers", class: "label",
+                        span { class: "label-text font-semibold text-base dark:text-white",
+                            "Callers"
+                        }
+                    }
+                    input {
+                        r#type: "number",
+                        min: "1",
+                        value: pile_up().callers,
+                        title: "More than one caller makes a pile-up",
+                        class: "input input-bordered w-full dark:text-white dark:bg-[#24283B]",
+                        id: "callers",
+                        oninput: move |e| {
+                            pile_up.write().callers = e.value().parse::<usize>().unwrap_or(1).max(1);
+                        },
+                    }
+                }
+                div { class: "form-control w-full",
+                    label { r#for: "min-wpm", class: "label",
+                        span { class: "label-text font-semibold text-base dark:text-white",
+                            "Min WPM"
+                        }
+                    }
+                    input {
+                        r#type: "number",
+                        value: pile_up().min_wpm,
+                        class: "input input-bordered w-full dark:text-white dark:bg-[#24283B]",
+                        id: "min-wpm",
+                        oninput: move |e| {
+                            pile_up.write().min_wpm = e.value().parse().unwrap_or_default();
+                        },
+                    }
+                }
+                div { class: "form-control w-full",
+                    label { r#for: "max-wpm", class: "label",
+                        span { class: "label-text font-semibold text-base dark:text-white",
+                            "Max WPM"
+                        }
+                    }
+                    input {
+                        r#type: "number",
+                        value: pile_up().max_wpm,
+                        class: "input input-bordered w-full dark:text-white dark:bg-[#24283B]",
+                        id: "max-wpm",
+                        oninput: move |e| {
+                            pile_up.write().max_wpm = e.value().parse().unwrap_or_default();
+                        },
+                    }
+                }
+                div { class: "form-control w-full",
+                    label { r#for: "frequency", class: "label",
+                        span { class: "label-text font-semibold text-base dark:text-white",
+                            "Frequency (Hz)"
+                        }
+                    }
+                    input {
+                        r#type: "number",
+                        value: pile_up().pitch,
+                        class: "input input-bordered w-full dark:text-white dark:bg-[#24283B]",
+                        id: "frequency",
+                        onchange: move |e| {
+                            pile_up.write().pitch = e.value().parse().unwrap_or_default();
+                        },
+                    }
+                }
+                div { class: "form-control w-full",
+                    label { r#for: "cut-numbers", class: "label",
+                        span { class: "label-text font-semibold text-base dark:text-white",
+                            "Cut Numbers"
+                        }
+                    }
+                    input {
+                        r#type: "checkbox",
+                        checked: generator.read().cut_numbers,
+                        title: "Send 9 as N and 0 as T",
+                        class: "toggle toggle-primary",
+                        id: "cut-numbers",
+                        onchange: move |e| {
+                            generator.write().cut_numbers = e.checked();
+                        },
+                    }
+                }
+            }
+            div { class: "flex gap-4 mb-6",
+                if is_playing() {
+                    button {
+                        class: "btn",
+                        id: "stop-round",
+                        onclick: {
+                            let platform = platform.clone();
+                            move |_| platform.stop()
+                        },
+                        "Stop"
+                    }
+                } else {
+                    button {
+                        class: "btn btn-primary",
+                        id: "new-round",
+                        onclick: {
+                            let platform = platform.clone();
+                            move |_| {
+                                let next = {
+                                    let mut generator = generator.write();
+                                    let callers = generator.pile_up(&pile_up());
+                                    let sent = match callers.as_slice() {
+                                        [caller] => {
+                                            let exchange = generator.exchange(&caller.station);
+                                            format!("{} {exchange}", caller.station.callsign)
+                                        }
+                                        callers => callers
+                                            .iter()
+                                            .map(|caller| caller.station.callsign.as_str())
+                                            .collect::<Vec<_>>()
+                                            .join(" "),
+                                    };
+                                    Round { callers, sent }
+                                };
+                                copy.set(String::new());
+                                checked.set(false);
+                                is_playing.set(true);
+                                play_round(&platform, &next, is_playing, error);
+                                round.set(Some(next));
+                            }
+                        },
+                        if pile_up().callers > 1 { "New Pile-Up" } else { "New Caller" }
+                    }
+                    if let Some(current) = round() {
+                        button {
+                            class: "btn",
+                            id: "replay-round",
+                            onclick: {
+                                let platform = platform.clone();
+                                move |_| {
+                                    is_playing.set(true);
+                                    play_round(&platform, &current, is_playing, error);
+                                }
+                            },
+                            "Replay"
+                        }
+                    }
+                }
+            }
+            div { class: "form-control w-full mb-6",
+                label { r#for: "copy", class: "label",
+                    span { class: "label-text font-semibold text-lg dark:text-white", "Copy" }
+                }
+                textarea {
+                    class: "textarea textarea-bordered w-full h-24 bg-base-100 border-solid border-2 font-mono uppercase dark:text-white dark:bg-[#24283B]",
+                    id: "copy",
+                    disabled: round().is_none() || checked(),
+                    value: copy,
+                    oninput: move |e| copy.set(e.value()),
+                }
+                button {
+                    class: "btn btn-primary mt-4 self-start",
+                    id: "check-copy",
+                    disabled: round().is_none() || checked(),
+                    onclick: move |_| checked.set(true),
+                    "Check"
+                }
+            }
+            if let (Some(current), true) = (round(), checked()) {
+                Results { round: current, copy: copy() }
+            }
+        }
+    }
+}
+
+/// Each station heard, and whether the words it sent were copied.
+#[component]
+fn Results(round: Round, copy: String) -> Element {
+    let copy: Vec<String> = copy.split_whitespace().map(str::to_uppercase).collect();
+    let words: Vec<(String, bool)> = round
+        .sent
+        .split_whitespace()
+        .map(|word| (word.to_string(), copy.iter().any(|copied| copied == word)))
+        .collect();
+    let correct = words.iter().filter(|(_, copied)| *copied).count();
+
+    rsx! {
+        div { id: "results",
+            div {
+                role: "status",
+                class: if correct == words.len() { "alert alert-success mb-6" } else { "alert alert-warning mb-6" },
+                span { "{correct} of {words.len()} copied." }
+            }
+            p { class: "font-mono text-lg mb-6 flex flex-wrap gap-2", id: "sent",
+                for (word, copied) in words {
+                    span {
+                        class: if copied { "badge badge-lg badge-success" } else { "badge badge-lg badge-error" },
+                        "{word}"
+                    }
+                }
+            }
+            table { class: "table dark:text-white",
+                thead {
+                    tr {
+                        th { "Callsign" }
+                        th { "Country" }
+                        th { "Zone" }
+                        th { "Speed" }
+                        th { "Pitch" }
+                    }
+                }
+                tbody {
+                    for caller in round.callers {
+                        tr {
+                            td { class: "font-mono", "{caller.station.callsign}" }
+                            td {
+                                "{caller.station.country}"
+                                if let Some(region) = caller.station.region {
+                                    " ({region})"
+                                }
+                            }
+                            td { "{caller.station.zone}" }
+                            td { {format!("{:.0} WPM", 1.2 / caller.timing.dit().as_secs_f64())} }
+                            td { {format!("{:.0} Hz", caller.tone.frequency)} }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -1,14 +1,68 @@
+use std::ops::Range;
+
 use dioxus::prelude::*;
-#[cfg(target_arch = "wasm32")]
-use dot_dash::web::{copy_to_clipboard, play_morse, play_text};
-use morsify::{MorseCode, Options as MorseOptions};
+use dot_dash::{
+    alphabet::{Alphabet, Table},
+    backend::{Backend, Platform},
+    playback::{Completion, PlaybackOutcome, ProgressEvents},
+    progress::Progress,
+    timeline::ElementKind,
+    timing::Timing,
+    tone::Tone,
+    translate::{decode_with, encode_with, text_range, Translation},
+};
+use morsify::Options as MorseOptions;
+
+use super::ErrorAlert;
+use crate::Route;
+
+/// Clears `is_playing` once playback has ended, showing the error if it
+/// failed to start or to finish.
+pub(crate) fn await_playback(
+    played: dot_dash::Result<Completion>,
+    mut is_playing: Signal<bool>,
+    mut error: Signal<Option<String>>,
+) {
+    spawn(async move {
+        let outcome = match played {
+            Ok(completion) => completion.await,
+            Err(err) => PlaybackOutcome::Failed(err),
+        };
+        is_playing.set(false);
+        if let PlaybackOutcome::Failed(err) = outcome {
+            error.set(Some(err.to_string()));
+        }
+    });
+}
+
+/// Keeps `progress` on the element being played, clearing it once playback
+/// has ended.
+fn follow_progress(mut events: ProgressEvents, mut progress: Signal<Option<Progress>>) {
+    spawn(async move {
+        while let Some(event) = events.next().await {
+            progress.set(Some(event));
+        }
+        progress.set(None);
+    });
+}
+
+/// Copies `text` to the clipboard, showing the error if that fails.
+fn copy(platform: Platform, text: String, mut error: Signal<Option<String>>) {
+    spawn(async move {
+        if let Err(err) = platform.copy_to_clipboard(&text).await {
+            error.set(Some(err.to_string()));
+        }
+    });
+}
 
 #[component]
 pub fn Home() -> Element {
     let mut text = use_signal(String::new);
     let mut morse = use_signal(String::new);
     let show_options = use_signal(|| false);
-    let morse_code = use_signal(MorseCode::default);
+    let mut error = use_signal(|| None::<String>);
+    let progress = use_signal(|| None::<Progress>);
+    use_context_provider(Platform::default);
 
     let morse_opts = use_signal(|| MorseOptions {
         dot: '.',
@@ -19,17 +73,51 @@ pub fn Home() -> Element {
     });
 
     let wpm = use_signal(|| 20);
+    let farnsworth_wpm = use_signal(|| 20);
     let frequency = use_signal(|| 700);
+    let vibrate = use_signal(|| false);
+    let prosigns = use_signal(|| false);
+    let table = use_signal(|| None::<Table>);
+    // `None` follows the script of the text.
+    let alphabet = use_signal(|| None::<Alphabet>);
+    let translation = use_memo(move || Translation {
+        prosigns: prosigns(),
+        table: table(),
+        alphabet: alphabet()
+            .or_else(|| Alphabet::detect(&text()))
+            .unwrap_or_default(),
+    });
+    // A table may clash with the letters of the alphabet it is used with.
+    use_effect(move || {
+        let translation = translation.read();
+        if let Some(Err(err)) = translation
+            .table
+            .as_ref()
+            .map(|table| table.check_alphabet(translation.alphabet))
+        {
+            error.set(Some(err.to_string()));
+        }
+    });
+    let timing = use_memo(move || {
+        Timing::farnsworth(
+            u32::try_from(wpm()).unwrap_or_default(),
+            u32::try_from(farnsworth_wpm()).unwrap_or_default(),
+        )
+    });
 
     rsx! {
         div { class: "w-full mx-auto h-4/5 p-5",
+            ErrorAlert { error }
             div { class: "grid grid-cols-1 md:grid-cols-2 gap-6",
                 TextFormControl {
                     text,
+                    translation,
+                    error,
+                    progress,
                     ontextinput: move |e: Event<FormData>| {
                         let value: String = e.value();
                         text.set(value.clone());
-                        morse.set(morse_code().encode(&value));
+                        morse.set(encode_with(&value, &morse_opts(), &translation()));
                     },
                 }
 
@@ -38,9 +126,14 @@ pub fn Home() -> Element {
                     ontextinput: move |e: Event<FormData>| {
                         let value: String = e.value();
                         morse.set(value.clone());
-                        text.set(morse_code().decode(&value));
+                        text.set(decode_with(&value, &morse_opts(), &translation()));
                     },
                     morse_opts,
+                    timing,
+                    frequency,
+                    vibrate,
+                    error,
+                    progress,
                 }
             }
             div { class: "grid grid-cols-1 gap-4 mt-6",
@@ -49,14 +142,40 @@ pub fn Home() -> Element {
                 } else {
                     ShowOptions { show_options }
                 }
+                div { class: "flex gap-6 justify-center",
+                    Link {
+                        class: "link link-info",
+                        id: "training",
+                        to: Route::Training {},
+                        "Koch Training"
+                    }
+                    Link {
+                        class: "link link-info",
+                        id: "practice",
+                        to: Route::Practice {},
+                        "Copy Practice"
+                    }
+                    Link {
+                        class: "link link-info",
+                        id: "contest",
+                        to: Route::Contest {},
+                        "Contest Practice"
+                    }
+                }
             }
             if show_options() {
                 Options {
                     morse_opts,
                     wpm,
+                    farnsworth_wpm,
                     frequency,
+                    vibrate,
+                    prosigns,
+                    table,
+                    alphabet,
+                    error,
                     oninput: move |_| {
-                        morse.set(morse_code().encode(text()));
+                        morse.set(encode_with(&text(), &morse_opts(), &translation()));
                     },
                 }
             }
@@ -65,8 +184,15 @@ pub fn Home() -> Element {
 }
 
 #[component]
-fn TextFormControl(text: Signal<String>, ontextinput: EventHandler<Event<FormData>>) -> Element {
+fn TextFormControl(
+    text: Signal<String>,
+    translation: Memo<Translation>,
+    ontextinput: EventHandler<Event<FormData>>,
+    error: Signal<Option<String>>,
+    progress: Signal<Option<Progress>>,
+) -> Element {
     let mut is_playing = use_signal(|| false);
+    let platform = use_context::<Platform>();
 
     rsx! {
         div { class: "flex flex-col",
@@ -77,11 +203,11 @@ fn TextFormControl(text: Signal<String>, ontextinput: EventHandler<Event<FormDat
                         title: "Play the Audio",
                         class: format!("tooltip cursor-pointer {}", if is_playing() { "hidden" } else { "" }),
                         id: "play-input",
-                        onclick: move |_| {
-                            is_playing.set(true);
-                            #[cfg(target_arch = "wasm32")]
-                            {
-                                play_text(text().as_str(), move || { is_playing.set(false) });
+                        onclick: {
+                            let platform = platform.clone();
+                            move |_| {
+                                is_playing.set(true);
+                                await_playback(platform.play_text(&text()), is_playing, error);
                             }
                         },
                         img {
@@ -95,6 +221,10 @@ fn TextFormControl(text: Signal<String>, ontextinput: EventHandler<Event<FormDat
                         title: "Stop the Audio",
                         class: format!("tooltip cursor-pointer {}", if is_playing() { "" } else { "hidden" }),
                         id: "stop-input",
+                        onclick: {
+                            let platform = platform.clone();
+                            move |_| platform.stop()
+                        },
                         img {
                             class: "fill-none dark:filter dark:invert",
                             src: asset!("/public/images/stop.svg"),
@@ -109,12 +239,7 @@ fn TextFormControl(text: Signal<String>, ontextinput: EventHandler<Event<FormDat
                         "data-copied": "Copied!",
                         title: "Click to Copy",
                         class: "tooltip cursor-pointer clipboard",
-                        onclick: move |_| {
-                            #[cfg(target_arch = "wasm32")]
-                            {
-                                copy_to_clipboard(text().as_str());
-                            }
-                        },
+                        onclick: move |_| copy(platform.clone(), text(), error),
                         img {
                             class: "fill-none dark:filter dark:invert",
                             src: asset!("/public/images/clipboard.svg"),
@@ -124,11 +249,20 @@ fn TextFormControl(text: Signal<String>, ontextinput: EventHandler<Event<FormDat
                     }
                 }
             }
-            textarea {
-                class: "textarea textarea-bordered w-full h-96 bg-base-100 border-solid border-2 dark:text-white dark:bg-[#24283B]",
-                id: "input",
-                value: text,
-                oninput: move |e| ontextinput.call(e),
+            if let Some(range) = progress().and_then(|progress| text_range(&text(), progress.character, &translation())) {
+                Highlighted {
+                    class: "textarea textarea-bordered w-full h-96 bg-base-100 border-solid border-2 dark:text-white dark:bg-[#24283B]",
+                    id: "input",
+                    text: text(),
+                    range,
+                }
+            } else {
+                textarea {
+                    class: "textarea textarea-bordered w-full h-96 bg-base-100 border-solid border-2 dark:text-white dark:bg-[#24283B]",
+                    id: "input",
+                    value: text,
+                    oninput: move |e| ontextinput.call(e),
+                }
             }
         }
     }
@@ -139,31 +273,62 @@ fn MorseFormControl(
     morse: Signal<String>,
     ontextinput: EventHandler<Event<FormData>>,
     morse_opts: Signal<MorseOptions>,
+    timing: Memo<Timing>,
+    frequency: Signal<i64>,
+    vibrate: Signal<bool>,
+    error: Signal<Option<String>>,
+    progress: Signal<Option<Progress>>,
 ) -> Element {
     let mut is_playing = use_signal(|| false);
+    let platform = use_context::<Platform>();
 
     rsx! {
         div { class: "flex flex-col",
             label { r#for: "output", class: "label",
                 span { class: "label-text font-semibold text-lg dark:text-white", "Morse Code" }
-                span { class: "label-text-alt flex gap-4",
+                span { class: "label-text-alt flex gap-4 items-center",
+                    span {
+                        title: "Keying",
+                        class: format!(
+                            "inline-block w-4 h-4 rounded-full {}",
+                            if progress().is_some_and(|progress| progress.kind == ElementKind::On) {
+                                "bg-warning"
+                            } else {
+                                "bg-base-300"
+                            },
+                        ),
+                        id: "lamp",
+                    }
                     if !is_playing() {
                         span {
                             "aria-label": "Play the Audio",
                             title: "Play the Audio",
                             class: "cursor-pointer",
                             id: "play-output",
-                            onclick: move |_| {
-                                is_playing.set(true);
-                                #[cfg(target_arch = "wasm32")]
-                                {
-                                    play_morse(
-                                        &morse(),
-                                        morse_opts(),
-                                        700.0,
-                                        60,
-                                        move || { is_playing.set(false) },
-                                    );
+                            onclick: {
+                                let platform = platform.clone();
+                                move |_| {
+                                    is_playing.set(true);
+                                    let played = platform
+                                        .play_morse_with_progress(
+                                            &morse(),
+                                            morse_opts(),
+                                            Tone::new(frequency() as f32),
+                                            timing(),
+                                        )
+                                        .map(|(completion, events)| {
+                                            follow_progress(events, progress);
+                                            completion
+                                        });
+                                    let vibrated = if vibrate() && played.is_ok() {
+                                        platform.vibrate_morse(&morse(), &morse_opts(), &timing())
+                                    } else {
+                                        Ok(())
+                                    };
+                                    if let Err(err) = vibrated {
+                                        error.set(Some(err.to_string()));
+                                    }
+                                    await_playback(played, is_playing, error);
                                 }
                             },
                             img {
@@ -181,6 +346,10 @@ fn MorseFormControl(
                             title: "Stop the Audio",
                             class: "cursor-pointer",
                             id: "stop-output",
+                            onclick: {
+                                let platform = platform.clone();
+                                move |_| platform.stop()
+                            },
                             img {
                                 class: "fill-none dark:filter dark:invert",
                                 src: asset!("/public/images/stop.svg"),
@@ -195,12 +364,7 @@ fn MorseFormControl(
                         title: "Click to Copy",
                         "data-clipboard-target": "#output",
                         class: "tooltip cursor-pointer clipboard",
-                        onclick: move |_| {
-                            #[cfg(target_arch = "wasm32")]
-                            {
-                                copy_to_clipboard(morse().as_str());
-                            }
-                        },
+                        onclick: move |_| copy(platform.clone(), morse(), error),
 
                         img {
                             class: "fill-none dark:filter dark:invert",
@@ -211,18 +375,44 @@ fn MorseFormControl(
                     }
                 }
             }
-            textarea {
-                class: "textarea textarea-bordered w-full h-96 lg:h-full bg-base-100 border-solid border-2 dark:text-white dark:bg-[#24283B]",
-                id: "output",
-                value: morse,
-                oninput: move |e| {
-                    ontextinput.call(e);
-                },
+            if let Some(progress) = progress() {
+                Highlighted {
+                    class: "textarea textarea-bordered w-full h-96 lg:h-full bg-base-100 border-solid border-2 dark:text-white dark:bg-[#24283B]",
+                    id: "output",
+                    text: morse(),
+                    range: progress.morse,
+                }
+            } else {
+                textarea {
+                    class: "textarea textarea-bordered w-full h-96 lg:h-full bg-base-100 border-solid border-2 dark:text-white dark:bg-[#24283B]",
+                    id: "output",
+                    value: morse,
+                    oninput: move |e| {
+                        ontextinput.call(e);
+                    },
+                }
             }
         }
     }
 }
 
+/// Stands in for a text area while a message plays, marking the character
+/// being sent.
+#[component]
+fn Highlighted(class: String, id: String, text: String, range: Range<usize>) -> Element {
+    let before = text.get(..range.start).unwrap_or_default();
+    let current = text.get(range.clone()).unwrap_or_default();
+    let after = text.get(range.end..).unwrap_or_default();
+
+    rsx! {
+        div { class: "{class} whitespace-pre-wrap break-words overflow-y-auto", id: "{id}",
+            "{before}"
+            mark { class: "bg-warning text-warning-content", "{current}" }
+            "{after}"
+        }
+    }
+}
+
 #[component]
 fn ShowOptions(show_options: Signal<bool>) -> Element {
     rsx! {
@@ -273,7 +463,13 @@ fn HideOptions(show_options: Signal<bool>) -> Element {
 fn Options(
     morse_opts: Signal<MorseOptions>,
     wpm: Signal<i64>,
+    farnsworth_wpm: Signal<i64>,
     frequency: Signal<i64>,
+    vibrate: Signal<bool>,
+    prosigns: Signal<bool>,
+    table: Signal<Option<Table>>,
+    alphabet: Signal<Option<Alphabet>>,
+    error: Signal<Option<String>>,
     oninput: EventHandler<Event<FormData>>,
 ) -> Element {
     rsx! {
@@ -385,6 +581,23 @@ fn Options(
                         },
                     }
                 }
+                div { class: "form-control w-full",
+                    label { r#for: "farnsworth-wpm", class: "label",
+                        span { class: "label-text font-semibold text-base dark:text-white",
+                            "Farnsworth WPM"
+                        }
+                    }
+                    input {
+                        r#type: "number",
+                        value: farnsworth_wpm,
+                        class: "input input-bordered w-full dark:text-white dark:bg-[#24283B]",
+                        id: "farnsworth-wpm",
+                        oninput: move |e| {
+                            farnsworth_wpm.set(e.value().parse::<i64>().unwrap_or_default());
+                            oninput(e);
+                        },
+                    }
+                }
                 div { class: "form-control w-full",
                     label { r#for: "frequency", class: "label",
                         span { class: "label-text font-semibold text-base dark:text-white",
@@ -402,6 +615,99 @@ fn Options(
                         },
                     }
                 }
+                div { class: "form-control w-full",
+                    label { r#for: "vibrate", class: "label",
+                        span { class: "label-text font-semibold text-base dark:text-white",
+                            "Vibrate"
+                        }
+                    }
+                    input {
+                        r#type: "checkbox",
+                        checked: vibrate,
+                        class: "toggle toggle-primary",
+                        id: "vibrate",
+                        onchange: move |e| vibrate.set(e.checked()),
+                    }
+                }
+                div { class: "form-control w-full",
+                    label { r#for: "prosigns", class: "label",
+                        span { class: "label-text font-semibold text-base dark:text-white",
+                            "Prosigns"
+                        }
+                    }
+                    input {
+                        r#type: "checkbox",
+                        checked: prosigns,
+                        title: "Send prosigns written as <AR> as one character",
+                        class: "toggle toggle-primary",
+                        id: "prosigns",
+                        onchange: move |e| {
+                            prosigns.set(e.checked());
+                            oninput(e);
+                        },
+                    }
+                }
+                div { class: "form-control w-full",
+                    label { r#for: "alphabet", class: "label",
+                        span { class: "label-text font-semibold text-base dark:text-white",
+                            "Alphabet"
+                        }
+                    }
+                    select {
+                        class: "select select-bordered w-full dark:text-white dark:bg-[#24283B]",
+                        id: "alphabet",
+                        onchange: move |e| {
+                            alphabet.set(Alphabet::from_id(&e.value()));
+                            oninput(e);
+                        },
+                        option { value: "auto", selected: alphabet().is_none(), "Automatic" }
+                        for choice in Alphabet::ALL {
+                            option {
+                                value: choice.id(),
+                                selected: alphabet() == Some(choice),
+                                {choice.name()}
+                            }
+                        }
+                    }
+                }
+                div { class: "form-control w-full",
+                    label { r#for: "table", class: "label",
+                        span { class: "label-text font-semibold text-base dark:text-white",
+                            "Alphabet Table"
+                        }
+                    }
+                    input {
+                        r#type: "file",
+                        accept: ".toml,.json",
+                        title: "A TOML or JSON file of extra characters and their patterns",
+                        class: "file-input file-input-bordered w-full",
+                        id: "table",
+                        onchange: move |e| async move {
+                            let Some(file) = e.files().into_iter().next() else {
+                                table.set(None);
+                                oninput(e);
+                                return;
+                            };
+                            let loaded = match file.read_string().await {
+                                Ok(source) if file.name().ends_with(".json") => {
+                                    Table::from_json(&source)
+                                }
+                                Ok(source) => Table::from_toml(&source),
+                                Err(err) => {
+                                    error.set(Some(format!("cannot read {}: {err}", file.name())));
+                                    return;
+                                }
+                            };
+                            match loaded {
+                                Ok(loaded) => {
+                                    table.set(Some(loaded));
+                                    oninput(e);
+                                }
+                                Err(err) => error.set(Some(err.to_string())),
+                            }
+                        },
+                    }
+                }
             }
         }
     }
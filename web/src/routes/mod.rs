@@ -1,2 +1,29 @@
+use dioxus::prelude::*;
+
+pub mod contest;
 pub mod home;
 pub mod page_not_found;
+pub mod practice;
+pub mod training;
+
+/// A seed for generating exercises, different on every call.
+pub(crate) fn random_seed() -> u64 {
+    (js_sys::Math::random() * u64::MAX as f64) as u64
+}
+
+/// The message in `error`, if there is one, with a button to dismiss it.
+#[component]
+pub(crate) fn ErrorAlert(mut error: Signal<Option<String>>) -> Element {
+    rsx! {
+        if let Some(message) = error() {
+            div { role: "alert", class: "alert alert-error mb-6", id: "error",
+                span { "{message}" }
+                button {
+                    class: "btn btn-sm btn-ghost",
+                    onclick: move |_| error.set(None),
+                    "Dismiss"
+                }
+            }
+        }
+    }
+}
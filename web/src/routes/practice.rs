@@ -0,0 +1,353 @@
+use std::time::Duration;
+
+use dioxus::prelude::*;
+use dot_dash::{
+    backend::{Backend, Platform},
+    practice::{Attempt, CharacterStats, Edit, Material, Session, Stats, STATS_KEY},
+    timing::Timing,
+    tone::Tone,
+    translate::encode,
+};
+use morsify::Options as MorseOptions;
+
+use super::{home::await_playback, random_seed, ErrorAlert};
+use crate::Route;
+
+/// The number of weakest characters listed after a check.
+const WEAKEST: usize = 10;
+
+/// The statistics saved by earlier sessions, or empty ones if there are
+/// none.
+fn load_stats(platform: &Platform) -> dot_dash::Result<Stats> {
+    platform
+        .read_setting(STATS_KEY)?
+        .map_or_else(|| Ok(Stats::default()), |json| Stats::from_json(&json))
+}
+
+/// Milliseconds on the page clock, for timing the copy.
+fn now() -> f64 {
+    js_sys::Date::now()
+}
+
+/// Keeps one typing time per character of the copy as it changes from
+/// `old` to `new`. Characters before the first change keep their times and
+/// the rest are stamped with `at`.
+fn note_typing(typed_at: &mut Vec<Duration>, old: &str, new: &str, at: Duration) {
+    let kept = old
+        .chars()
+        .zip(new.chars())
+        .take_while(|(old, new)| old == new)
+        .count();
+    typed_at.truncate(kept);
+    typed_at.resize(new.chars().count(), at);
+}
+
+#[component]
+pub fn Practice() -> Element {
+    let platform = use_context_provider(Platform::default);
+    let (stats, load_error) = use_hook(|| match load_stats(&platform) {
+        Ok(stats) => (stats, None),
+        Err(err) => (Stats::default(), Some(err.to_string())),
+    });
+    let mut error = use_signal(|| load_error);
+    let mut session = use_signal(|| Session::new(Material::default(), stats, random_seed()));
+    let mut exercise = use_signal(|| None::<String>);
+    let mut copy = use_signal(String::new);
+    let mut typed_at = use_signal(Vec::<Duration>::new);
+    let mut started = use_signal(|| 0.0);
+    let mut attempt = use_signal(|| None::<Attempt>);
+    let mut is_playing = use_signal(|| false);
+    let mut items = use_signal(|| 5);
+    let mut wpm = use_signal(|| 20);
+    let mut farnsworth_wpm = use_signal(|| 20);
+    let mut frequency = use_signal(|| 700);
+    let timing = use_memo(move || {
+        Timing::farnsworth(
+            u32::try_from(wpm()).unwrap_or_default(),
+            u32::try_from(farnsworth_wpm()).unwrap_or_default(),
+        )
+    });
+
+    let play = {
+        let platform = platform.clone();
+        move |text: &str| {
+            let options = MorseOptions::default();
+            is_playing.set(true);
+            started.set(now());
+            let played = platform.play_morse(
+                &encode(text, &options),
+                options,
+                Tone::new(frequency() as f32),
+                timing(),
+            );
+            await_playback(played, is_playing, error);
+        }
+    };
+
+    let save = {
+        let platform = platform.clone();
+        move || {
+            if let Err(err) = platform.write_setting(STATS_KEY, &session.read().stats.to_json()) {
+                error.set(Some(err.to_string()));
+            }
+        }
+    };
+
+    rsx! {
+        div { class: "w-full mx-auto h-4/5 p-5",
+            ErrorAlert { error }
+            div { class: "flex justify-between items-center mb-6",
+                h1 { class: "text-lg sm:text-2xl font-bold dark:text-white", "Copy Practice" }
+                Link { class: "link link-info", to: Route::Home {}, "Translator" }
+            }
+            div { class: "grid grid-cols-2 md:grid-cols-5 gap-6 mb-6",
+                div { class: "form-control w-full",
+                    label { r#for: "material", class: "label",
+                        span { class: "label-text font-semibold text-base dark:text-white",
+                            "Material"
+                        }
+                    }
+                    select {
+                        class: "select select-bordered w-full dark:text-white dark:bg-[#24283B]",
+                        id: "material",
+                        onchange: move |e| {
+                            let index = e.value().parse::<usize>().unwrap_or_default();
+                            session.write().material = Material::ALL[index.min(Material::ALL.len() - 1)];
+                        },
+                        for (index, material) in Material::ALL.into_iter().enumerate() {
+                            option {
+                                value: index.to_string(),
+                                selected: session.read().material == material,
+                                {material.name()}
+                            }
+                        }
+                    }
+                }
+                div { class: "form-control w-full",
+                    label { r#for: "items", class: "label",
+                        span { class: "label-text font-semibold text-base dark:text-white",
+                            "Items"
+                        }
+                    }
+                    input {
+                        r#type: "number",
+                        min: "1",
+                        value: items,
+                        class: "input input-bordered w-full dark:text-white dark:bg-[#24283B]",
+                        id: "items",
+                        oninput: move |e| items.set(e.value().parse::<usize>().unwrap_or(1).max(1)),
+                    }
+                }
+                div { class: "form-control w-full",
+                    label { r#for: "wpm", class: "label",
+                        span { class: "label-text font-semibold text-base dark:text-white",
+                            "WPM"
+                        }
+                    }
+                    input {
+                        r#type: "number",
+                        value: wpm,
+                        class: "input input-bordered w-full dark:text-white dark:bg-[#24283B]",
+                        id: "wpm",
+                        oninput: move |e| wpm.set(e.value().parse::<i64>().unwrap_or_default()),
+                    }
+                }
+                div { class: "form-control w-full",
+                    label { r#for: "farnsworth-wpm", class: "label",
+                        span { class: "label-text font-semibold text-base dark:text-white",
+                            "Farnsworth WPM"
+                        }
+                    }
+                    input {
+                        r#type: "number",
+                        value: farnsworth_wpm,
+                        class: "input input-bordered w-full dark:text-white dark:bg-[#24283B]",
+                        id: "farnsworth-wpm",
+                        oninput: move |e| farnsworth_wpm.set(e.value().parse::<i64>().unwrap_or_default()),
+                    }
+                }
+                div { class: "form-control w-full",
+                    label { r#for: "frequency", class: "label",
+                        span { class: "label-text font-semibold text-base dark:text-white",
+                            "Frequency (Hz)"
+                        }
+                    }
+                    input {
+                        r#type: "number",
+                        value: frequency,
+                        class: "input input-bordered w-full dark:text-white dark:bg-[#24283B]",
+                        id: "frequency",
+                        onchange: move |e| frequency.set(e.value().parse::<i64>().unwrap_or_default()),
+                    }
+                }
+            }
+            div { class: "flex gap-4 mb-6",
+                if is_playing() {
+                    button {
+                        class: "btn",
+                        id: "stop-exercise",
+                        onclick: {
+                            let platform = platform.clone();
+                            move |_| platform.stop()
+                        },
+                        "Stop"
+                    }
+                } else {
+                    button {
+                        class: "btn btn-primary",
+                        id: "new-exercise",
+                        onclick: {
+                            let mut play = play.clone();
+                            move |_| {
+                                let text = session.write().exercise(items());
+                                copy.set(String::new());
+                                typed_at.set(Vec::new());
+                                attempt.set(None);
+                                play(&text);
+                                exercise.set(Some(text));
+                            }
+                        },
+                        "New Exercise"
+                    }
+                    if let Some(text) = exercise() {
+                        button {
+                            class: "btn",
+                            id: "replay-exercise",
+                            onclick: {
+                                let mut play = play.clone();
+                                move |_| {
+                                    // Response times only count from the first playing.
+                                    let first = started();
+                                    play(&text);
+                                    started.set(first);
+                                }
+                            },
+                            "Replay"
+                        }
+                    }
+                }
+                button {
+                    class: "btn btn-ghost ml-auto",
+                    id: "reset-stats",
+                    onclick: {
+                        let mut save = save.clone();
+                        move |_| {
+                            session.write().stats = Stats::default();
+                            save();
+                        }
+                    },
+                    "Reset Statistics"
+                }
+            }
+            div { class: "form-control w-full mb-6",
+                label { r#for: "copy", class: "label",
+                    span { class: "label-text font-semibold text-lg dark:text-white", "Copy" }
+                }
+                textarea {
+                    class: "textarea textarea-bordered w-full h-40 bg-base-100 border-solid border-2 font-mono uppercase dark:text-white dark:bg-[#24283B]",
+                    id: "copy",
+                    disabled: exercise().is_none() || attempt().is_some(),
+                    value: copy,
+                    oninput: move |e| {
+                        let value = e.value();
+                        let at = Duration::from_secs_f64(((now() - started()) / 1000.0).max(0.0));
+                        note_typing(&mut typed_at.write(), &copy.read(), &value, at);
+                        copy.set(value);
+                    },
+                }
+                button {
+                    class: "btn btn-primary mt-4 self-start",
+                    id: "check-copy",
+                    disabled: exercise().is_none() || attempt().is_some(),
+                    onclick: {
+                        let mut save = save.clone();
+                        move |_| {
+                            if let Some(text) = exercise() {
+                                let result = session.write().check(
+                                    &text,
+                                    &copy(),
+                                    &typed_at(),
+                                    &MorseOptions::default(),
+                                    &timing(),
+                                );
+                                attempt.set(Some(result));
+                                save();
+                            }
+                        }
+                    },
+                    "Check"
+                }
+            }
+            if let Some(attempt) = attempt() {
+                Results { attempt, stats: session.read().stats.clone() }
+            }
+        }
+    }
+}
+
+/// The copy marked against the exercise, and the characters that need the
+/// most practice.
+#[component]
+fn Results(attempt: Attempt, stats: Stats) -> Element {
+    let accuracy = attempt.accuracy() * 100.0;
+    let mut weakest: Vec<(char, CharacterStats)> = stats
+        .iter()
+        .map(|(char, character)| (char, *character))
+        .collect();
+    weakest.sort_by(|a, b| stats.weight(b.0).total_cmp(&stats.weight(a.0)));
+    weakest.truncate(WEAKEST);
+
+    rsx! {
+        div { id: "results",
+            div { role: "status", class: "alert mb-6",
+                span { "{attempt.copied()} of {attempt.sent()} characters copied ({accuracy:.0}%)." }
+            }
+            p { class: "font-mono text-lg mb-6 whitespace-pre-wrap break-words dark:text-white", id: "diff",
+                for edit in attempt.edits().iter().copied() {
+                    {
+                        match edit {
+                            Edit::Match(char) => rsx! { "{char}" },
+                            Edit::Substitute { sent, copied } => rsx! {
+                                del { class: "text-error", title: "Copied", "{copied}" }
+                                ins { class: "text-success", title: "Sent", "{sent}" }
+                            },
+                            Edit::Missed(sent) => rsx! {
+                                ins { class: "text-success", title: "Missed", "{sent}" }
+                            },
+                            Edit::Extra(copied) => rsx! {
+                                del { class: "text-error", title: "Not sent", "{copied}" }
+                            },
+                        }
+                    }
+                }
+            }
+            h2 { class: "text-lg font-bold mb-2 dark:text-white", "Needs Practice" }
+            table { class: "table dark:text-white", id: "weakest",
+                thead {
+                    tr {
+                        th { "Character" }
+                        th { "Sent" }
+                        th { "Errors" }
+                        th { "Response" }
+                    }
+                }
+                tbody {
+                    for (char, character) in weakest {
+                        tr {
+                            td { class: "font-mono", "{char}" }
+                            td { "{character.sent()}" }
+                            td { {format!("{:.0}%", character.error_rate().unwrap_or_default() * 100.0)} }
+                            td {
+                                {
+                                    character
+                                        .mean_latency()
+                                        .map_or_else(|| "–".to_string(), |latency| format!("{} ms", latency.as_millis()))
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
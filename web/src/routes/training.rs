@@ -0,0 +1,253 @@
+use dioxus::prelude::*;
+use dot_dash::{
+    backend::{Backend, Platform},
+    tone::Tone,
+    training::{Koch, Lesson, Score, LEVEL_KEY, PASS_ACCURACY},
+    translate::encode,
+};
+use morsify::Options as MorseOptions;
+
+use super::{home::await_playback, random_seed, ErrorAlert};
+use crate::Route;
+
+/// The number of five-character groups in a lesson.
+const LESSON_GROUPS: usize = 10;
+
+/// The training level saved by earlier sessions, or the first one if none
+/// was saved.
+fn load_koch(platform: &Platform) -> dot_dash::Result<Koch> {
+    platform.read_setting(LEVEL_KEY)?.map_or_else(
+        || Ok(Koch::default()),
+        |setting| Koch::from_setting(&setting),
+    )
+}
+
+/// Plays `lesson` at the speeds of `koch`.
+fn play_lesson(
+    platform: &Platform,
+    lesson: &Lesson,
+    koch: Koch,
+    frequency: i64,
+    is_playing: Signal<bool>,
+    error: Signal<Option<String>>,
+) {
+    let options = MorseOptions::default();
+    let played = platform.play_morse(
+        &encode(&lesson.text(), &options),
+        options,
+        Tone::new(frequency as f32),
+        koch.timing(),
+    );
+    await_playback(played, is_playing, error);
+}
+
+#[component]
+pub fn Training() -> Element {
+    let platform = use_context_provider(Platform::default);
+    let (saved, load_error) = use_hook(|| match load_koch(&platform) {
+        Ok(koch) => (koch, None),
+        Err(err) => (Koch::default(), Some(err.to_string())),
+    });
+    let mut koch = use_signal(|| saved);
+    let mut lesson = use_signal(|| None::<Lesson>);
+    let mut copy = use_signal(String::new);
+    let mut score = use_signal(|| None::<Score>);
+    let mut unlocked = use_signal(|| None::<char>);
+    let mut is_playing = use_signal(|| false);
+    let mut error = use_signal(|| load_error);
+    let mut frequency = use_signal(|| 700);
+
+    rsx! {
+        div { class: "w-full mx-auto h-4/5 p-5",
+            ErrorAlert { error }
+            div { class: "flex justify-between items-center mb-6",
+                h1 { class: "text-lg sm:text-2xl font-bold dark:text-white", "Koch Training" }
+                Link { class: "link link-info", to: Route::Home {}, "Translator" }
+            }
+            div { class: "flex flex-wrap gap-2 mb-6", id: "characters",
+                for char in koch().characters().chars() {
+                    span {
+                        class: if char == koch().newest() { "badge badge-lg badge-primary" } else { "badge badge-lg" },
+                        "{char}"
+                    }
+                }
+            }
+            div { class: "grid grid-cols-2 md:grid-cols-3 gap-6 mb-6",
+                div { class: "form-control w-full",
+                    label { r#for: "character-wpm", class: "label",
+                        span { class: "label-text font-semibold text-base dark:text-white",
+                            "Character WPM"
+                        }
+                    }
+                    input {
+                        r#type: "number",
+                        value: koch().character_wpm,
+                        class: "input input-bordered w-full dark:text-white dark:bg-[#24283B]",
+                        id: "character-wpm",
+                        oninput: move |e| {
+                            koch.write().character_wpm = e.value().parse().unwrap_or_default();
+                        },
+                    }
+                }
+                div { class: "form-control w-full",
+                    label { r#for: "effective-wpm", class: "label",
+                        span { class: "label-text font-semibold text-base dark:text-white",
+                            "Effective WPM"
+                        }
+                    }
+                    input {
+                        r#type: "number",
+                        value: koch().effective_wpm,
+                        class: "input input-bordered w-full dark:text-white dark:bg-[#24283B]",
+                        id: "effective-wpm",
+                        oninput: move |e| {
+                            koch.write().effective_wpm = e.value().parse().unwrap_or_default();
+                        },
+                    }
+                }
+                div { class: "form-control w-full",
+                    label { r#for: "frequency", class: "label",
+                        span { class: "label-text font-semibold text-base dark:text-white",
+                            "Frequency (Hz)"
+                        }
+                    }
+                    input {
+                        r#type: "number",
+                        value: frequency,
+                        class: "input input-bordered w-full dark:text-white dark:bg-[#24283B]",
+                        id: "frequency",
+                        onchange: move |e| frequency.set(e.value().parse::<i64>().unwrap_or_default()),
+                    }
+                }
+            }
+            div { class: "flex gap-4 mb-6",
+                if is_playing() {
+                    button {
+                        class: "btn",
+                        id: "stop-lesson",
+                        onclick: {
+                            let platform = platform.clone();
+                            move |_| platform.stop()
+                        },
+                        "Stop"
+                    }
+                } else {
+                    button {
+                        class: "btn btn-primary",
+                        id: "new-lesson",
+                        onclick: {
+                            let platform = platform.clone();
+                            move |_| {
+                                let next = koch().lesson(LESSON_GROUPS, random_seed());
+                                copy.set(String::new());
+                                score.set(None);
+                                unlocked.set(None);
+                                is_playing.set(true);
+                                play_lesson(&platform, &next, koch(), frequency(), is_playing, error);
+                                lesson.set(Some(next));
+                            }
+                        },
+                        "New Lesson"
+                    }
+                    if let Some(current) = lesson() {
+                        button {
+                            class: "btn",
+                            id: "replay-lesson",
+                            onclick: {
+                                let platform = platform.clone();
+                                move |_| {
+                                    is_playing.set(true);
+                                    play_lesson(&platform, &current, koch(), frequency(), is_playing, error);
+                                }
+                            },
+                            "Replay"
+                        }
+                    }
+                }
+            }
+            div { class: "form-control w-full mb-6",
+                label { r#for: "copy", class: "label",
+                    span { class: "label-text font-semibold text-lg dark:text-white", "Copy" }
+                }
+                textarea {
+                    class: "textarea textarea-bordered w-full h-40 bg-base-100 border-solid border-2 font-mono uppercase dark:text-white dark:bg-[#24283B]",
+                    id: "copy",
+                    disabled: lesson().is_none() || score().is_some(),
+                    value: copy,
+                    oninput: move |e| copy.set(e.value()),
+                }
+                button {
+                    class: "btn btn-primary mt-4 self-start",
+                    id: "check-copy",
+                    disabled: lesson().is_none() || score().is_some(),
+                    onclick: {
+                        let platform = platform.clone();
+                        move |_| {
+                            if let Some(current) = lesson() {
+                                let result = current.score(&copy());
+                                let next = koch.write().advance(&result);
+                                if next.is_some() {
+                                    if let Err(err) = platform.write_setting(LEVEL_KEY, &koch().to_setting()) {
+                                        error.set(Some(err.to_string()));
+                                    }
+                                }
+                                unlocked.set(next);
+                                score.set(Some(result));
+                            }
+                        }
+                    },
+                    "Check"
+                }
+            }
+            if let (Some(current), Some(score)) = (lesson(), score()) {
+                Results { lesson: current, score, unlocked: unlocked() }
+            }
+        }
+    }
+}
+
+/// The groups that were sent and how well each character was copied.
+#[component]
+fn Results(lesson: Lesson, score: Score, unlocked: Option<char>) -> Element {
+    let accuracy = score.total().accuracy().unwrap_or_default() * 100.0;
+    let pass = PASS_ACCURACY * 100.0;
+
+    rsx! {
+        div { id: "results",
+            div {
+                role: "status",
+                class: if score.passed() { "alert alert-success mb-6" } else { "alert alert-warning mb-6" },
+                if let Some(char) = unlocked {
+                    span { "{accuracy:.0}% copied. {char} is unlocked." }
+                } else if score.passed() {
+                    span { "{accuracy:.0}% copied. Every character is unlocked." }
+                } else {
+                    span { "{accuracy:.0}% copied. Reach {pass:.0}% to unlock the next character." }
+                }
+            }
+            p { class: "font-mono text-lg mb-6 dark:text-white", id: "sent",
+                "{lesson.text()}"
+            }
+            table { class: "table dark:text-white",
+                thead {
+                    tr {
+                        th { "Character" }
+                        th { "Sent" }
+                        th { "Copied" }
+                        th { "Accuracy" }
+                    }
+                }
+                tbody {
+                    for (char, tally) in score.iter() {
+                        tr {
+                            td { class: "font-mono", "{char}" }
+                            td { "{tally.sent}" }
+                            td { "{tally.correct}" }
+                            td { {format!("{:.0}%", tally.accuracy().unwrap_or_default() * 100.0)} }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}